@@ -1,94 +1,614 @@
-use clap::Parser;
+use std::collections::HashMap;
+
+use clap::{Parser, Subcommand};
 use serde::Deserialize;
 
-#[derive(Parser, Deserialize, Clone)]
+use crate::service::resource::ResourceType;
+use crate::service::secret::Secret;
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// 上传证书并部署到域名，然后发送通知，不指定子命令时默认执行
+    Run,
+    /// 只上传证书并输出 CertificateId，已上传过相同证书时直接输出其 CertificateId
+    Upload,
+    /// 把已上传的证书部署到各任务的域名
+    Deploy {
+        /// 要部署的证书ID，teo 部署方式可以指定多个，如同时部署 RSA 和 ECC 证书
+        #[arg(required = true)]
+        certificate_ids: Vec<String>,
+    },
+    /// 查询证书列表
+    List {
+        /// 搜索关键字，可以是证书ID、备注名称或域名
+        #[arg(long)]
+        search_key: Option<String>,
+        /// 证书状态，多个以英文逗号分割，0审核中 1已通过 2审核失败 3已过期 4已添加DNS记录 5企业证书待提交 6订单取消中 7已取消 8已提交资料 9吊销中 10已吊销 11重颁发中 12待上传吊销确认函
+        #[arg(long, value_delimiter = ',')]
+        status: Vec<i64>,
+        /// 只显示上传的证书
+        #[arg(long)]
+        upload_only: bool,
+        /// 只显示即将过期的证书
+        #[arg(long)]
+        expiring: bool,
+        /// 偏移量
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+        /// 每页数量，最大1000
+        #[arg(long, default_value_t = 100)]
+        limit: i64,
+    },
+    /// 查询部署记录详情
+    Status {
+        /// 部署记录ID
+        deploy_record_id: i64,
+    },
+    /// 删除证书
+    Delete {
+        /// 要删除的证书ID
+        certificate_id: String,
+    },
+    /// 清理被本地证书取代并且没有关联任何云资源的已上传证书
+    Cleanup {
+        /// 只输出要删除的证书，不实际删除
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 监听证书文件变化，证书和私钥匹配后自动上传部署
+    Watch,
+    /// 常驻运行，按 cron 计划定时上传部署，证书没有变化时跳过
+    Daemon,
+    /// 把各任务的域名恢复到最后一次部署前绑定的证书，需要状态文件
+    Rollback,
+    /// 检查本地证书和 EO 域名绑定的证书，即将到期或两者不一致时发送通知，不修改任何配置
+    Check,
+    /// 对比本地证书和 EO 域名当前绑定的证书，输出部署会带来的变化，不修改任何配置
+    Plan {
+        /// 以 JSON 格式输出
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Parser, Deserialize, Clone, Default)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    /// 不指定子命令时执行一次上传部署后退出
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
+    /// 配置文件路径，TOML 格式，可以声明多个部署任务, 环境变量 TENCENTCLOUD_CONFIG_FILE
+    #[arg(long)]
+    #[serde(skip)]
+    pub config_file: Option<String>,
+
     /// 密钥ID, 环境变量 TENCENTCLOUD_SECRET_ID
     #[arg(long)]
-    pub secret_id: Option<String>,
+    pub secret_id: Option<Secret<String>>,
     /// 密钥KEY, 环境变量 TENCENTCLOUD_SECRET_KEY
     #[arg(long)]
-    pub secret_key: Option<String>,
+    pub secret_key: Option<Secret<String>>,
+    /// 临时密钥的 token, 环境变量 TENCENTCLOUD_TOKEN
+    #[arg(long)]
+    pub token: Option<Secret<String>>,
+    /// 未设置密钥时读取 ~/.tencentcloud/credentials 中的哪个配置，默认 default, 环境变量 TENCENTCLOUD_PROFILE
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// 要扮演的角色 ARN，设置后使用上面获取到的密钥调用 STS AssumeRole 获取临时密钥, 环境变量 TENCENTCLOUD_ROLE_ARN
+    #[arg(long)]
+    pub role_arn: Option<String>,
+    /// OIDC 身份提供商名称，设置后使用 OIDC 令牌调用 STS AssumeRoleWithWebIdentity 扮演 --role-arn 角色, 环境变量 TENCENTCLOUD_OIDC_PROVIDER_ID
+    #[arg(long)]
+    pub oidc_provider_id: Option<String>,
+    /// OIDC 令牌文件路径，每次获取临时密钥时重新读取, 环境变量 TENCENTCLOUD_WEB_IDENTITY_TOKEN_FILE
+    #[arg(long)]
+    pub web_identity_token_file: Option<String>,
+    /// OIDC 令牌，未设置令牌文件时使用, 环境变量 TENCENTCLOUD_WEB_IDENTITY_TOKEN
+    #[arg(long)]
+    pub web_identity_token: Option<Secret<String>>,
+    /// 扮演角色的会话名称，默认 tc-eo-ssl, 环境变量 TENCENTCLOUD_ROLE_SESSION_NAME
+    #[arg(long)]
+    pub role_session_name: Option<String>,
     /// 公钥文件路径, 环境变量 TENCENTCLOUD_PUBLIC_KEY_FILE_PATH
     #[arg(long)]
     pub public_key_file_path: Option<String>,
     /// 私钥文件路径, 环境变量 TENCENTCLOUD_PRIVATE_KEY_FILE_PATH
     #[arg(long)]
     pub private_key_file_path: Option<String>,
-    /// 域名列表，多个域名以英文逗号分割, 环境变量 TENCENTCLOUD_INSTANCE_ID_LIST
+    /// ECC 公钥文件路径，与 RSA 证书同时部署到相同的域名，如 acme.sh 的 example.com_ecc 证书, 环境变量 TENCENTCLOUD_ECC_PUBLIC_KEY_FILE_PATH
+    #[arg(long)]
+    pub ecc_public_key_file_path: Option<String>,
+    /// ECC 私钥文件路径, 环境变量 TENCENTCLOUD_ECC_PRIVATE_KEY_FILE_PATH
+    #[arg(long)]
+    pub ecc_private_key_file_path: Option<String>,
+    /// 部署的资源类型 teo、cdn、clb、waf、live、ddos、apigateway、vod、tke、cos，默认 teo, 环境变量 TENCENTCLOUD_RESOURCE_TYPE
+    #[arg(long)]
+    pub resource_type: Option<String>,
+    /// 资源所在地域，如 ap-guangzhou，clb、apigateway、tke、cos 资源必须设置, 环境变量 TENCENTCLOUD_REGION
+    #[arg(long)]
+    pub region: Option<String>,
+    /// 域名列表，多个域名以英文逗号分割，非 EO 资源为对应格式的实例ID，如 clb 为 lb-xxxxxxxx|lbl-xxxxxxxx, 环境变量 TENCENTCLOUD_INSTANCE_ID_LIST
     #[arg(long)]
     pub instance_id_list: Option<Vec<String>>,
+    /// 是否自动发现 EO 中被证书覆盖的加速域名并部署，与域名列表同时设置时部署到两者的并集, 环境变量 TENCENTCLOUD_AUTO_DISCOVER true自动发现，false不自动发现，默认不自动发现
+    #[arg(long)]
+    pub auto_discover: Option<bool>,
+    /// 自动发现时只部署匹配这些通配符的域名，多个以英文逗号分割，如 *.example.com, 环境变量 TENCENTCLOUD_INCLUDE_DOMAINS
+    #[arg(long)]
+    pub include_domains: Option<Vec<String>>,
+    /// 自动发现时排除匹配这些通配符的域名，多个以英文逗号分割, 环境变量 TENCENTCLOUD_EXCLUDE_DOMAINS
+    #[arg(long)]
+    pub exclude_domains: Option<Vec<String>>,
+    /// 部署方式，ssl 通过 SSL 证书服务的 DeployCertificateInstance 异步部署，teo 通过 EO 的 ModifyHostsCertificate 按站点同步部署，默认 ssl, 环境变量 TENCENTCLOUD_DEPLOY_BACKEND
+    #[arg(long)]
+    pub deploy_backend: Option<String>,
+    /// teo 部署方式下某个站点配置证书失败时，是否把该站点的域名切换为 EO 免费证书, 环境变量 TENCENTCLOUD_EO_FREE_CERT_FALLBACK true切换，false不切换，默认不切换
+    #[arg(long)]
+    pub eo_free_cert_fallback: Option<bool>,
+    /// 是否使用一键更新，不按域名部署，而是把绑定旧证书的云资源都更换为新证书, 环境变量 TENCENTCLOUD_REPLACE true一键更新，false按域名部署，默认按域名部署
+    #[arg(long)]
+    pub replace: Option<bool>,
+    /// 一键更新的资源类型，多个以英文逗号分割，默认为 --resource-type, 环境变量 TENCENTCLOUD_REPLACE_RESOURCE_TYPES
+    #[arg(long)]
+    pub replace_resource_types: Option<Vec<String>>,
+    /// 部署成功后是否删除被新证书取代并且没有关联任何云资源的已上传证书，需要能确认部署成功，即 teo 部署方式、一键更新或等待部署完成, 环境变量 TENCENTCLOUD_CLEANUP true删除，false不删除，默认不删除
+    #[arg(long)]
+    pub cleanup: Option<bool>,
+    /// 清理旧证书时保留最近的几个旧证书，默认0, 环境变量 TENCENTCLOUD_CLEANUP_KEEP
+    #[arg(long)]
+    pub cleanup_keep: Option<usize>,
     /// 是否使用国际站, 环境变量 TENCENTCLOUD_INTL true国际站，false国内站，默认国内站
     #[arg(long)]
     pub intl: Option<bool>,
+    /// 是否等待部署完成, 环境变量 TENCENTCLOUD_DEPLOY_WAIT true等待，false不等待，默认不等待
+    #[arg(long)]
+    pub deploy_wait: Option<bool>,
+    /// 等待部署完成的超时时间，单位秒，默认600, 环境变量 TENCENTCLOUD_DEPLOY_WAIT_TIMEOUT
+    #[arg(long)]
+    pub deploy_wait_timeout: Option<u64>,
+    /// 等待部署完成时如果部署失败或超时，是否自动回滚到部署前的证书, 环境变量 TENCENTCLOUD_AUTO_ROLLBACK true回滚，false不回滚，默认不回滚
+    #[arg(long)]
+    pub auto_rollback: Option<bool>,
     
+    /// check 子命令中证书剩余天数不超过多少天时告警，默认14, 环境变量 TENCENTCLOUD_EXPIRY_WARNING_DAYS
+    #[arg(long)]
+    pub expiry_warning_days: Option<i64>,
+
     /// TG bot token, 环境变量 TELEGRAM_BOT_TOKEN
     #[arg(long)]
-    pub tg_bot_token: Option<String>,
+    pub tg_bot_token: Option<Secret<String>>,
     /// TG 聊天ID, 环境变量 TELEGRAM_CHAT_ID
     #[arg(long)]
     pub tg_chat_id: Option<i64>,
     /// TG 聊天主题ID，默认0, 环境变量 TELEGRAM_TOPIC_ID
     #[arg(long)]
     pub tg_topic_id: Option<i64>,
+    /// TG Bot API 地址，使用自建的 Bot API 服务时配置，默认 https://api.telegram.org, 环境变量 TELEGRAM_API_BASE_URL
+    #[arg(long)]
+    pub tg_api_base_url: Option<String>,
+    /// TG 成功消息是否静默发送，失败消息始终正常提醒, 环境变量 TELEGRAM_SILENT_SUCCESS true静默，false正常提醒，默认正常提醒
+    #[arg(long)]
+    pub tg_silent_success: Option<bool>,
+    /// 常驻模式下接受 TG 机器人命令的聊天ID，多个以英文逗号分割，配置后才启用命令, 环境变量 TELEGRAM_COMMAND_CHAT_IDS
+    #[arg(long, value_delimiter = ',')]
+    pub tg_command_chat_ids: Option<Vec<i64>>,
+    /// 企业微信群机器人 webhook 地址, 环境变量 WECOM_WEBHOOK_URL
+    #[arg(long)]
+    pub wecom_webhook_url: Option<Secret<String>>,
+    /// 钉钉群机器人 webhook 地址, 环境变量 DINGTALK_WEBHOOK_URL
+    #[arg(long)]
+    pub dingtalk_webhook_url: Option<Secret<String>>,
+    /// 钉钉群机器人加签密钥，机器人安全设置使用加签时配置, 环境变量 DINGTALK_SECRET
+    #[arg(long)]
+    pub dingtalk_secret: Option<Secret<String>>,
+    /// 飞书/Lark 自定义机器人 webhook 地址, 环境变量 FEISHU_WEBHOOK_URL
+    #[arg(long)]
+    pub feishu_webhook_url: Option<Secret<String>>,
+    /// 飞书/Lark 自定义机器人签名校验密钥，机器人安全设置使用签名校验时配置, 环境变量 FEISHU_SECRET
+    #[arg(long)]
+    pub feishu_secret: Option<Secret<String>>,
+    /// SMTP 服务器地址，配置了服务器、发件人和收件人才发送邮件通知, 环境变量 SMTP_HOST
+    #[arg(long)]
+    pub smtp_host: Option<String>,
+    /// SMTP 端口，默认按加密方式 starttls 587、tls 465、none 25, 环境变量 SMTP_PORT
+    #[arg(long)]
+    pub smtp_port: Option<u16>,
+    /// SMTP 加密方式 starttls、tls、none，默认 starttls, 环境变量 SMTP_SECURITY
+    #[arg(long)]
+    pub smtp_security: Option<String>,
+    /// SMTP 用户名，不配置时不认证, 环境变量 SMTP_USERNAME
+    #[arg(long)]
+    pub smtp_username: Option<String>,
+    /// SMTP 密码或授权码, 环境变量 SMTP_PASSWORD
+    #[arg(long)]
+    pub smtp_password: Option<Secret<String>>,
+    /// 发件人，如 tc-eo-ssl <noreply@example.com>, 环境变量 SMTP_FROM
+    #[arg(long)]
+    pub smtp_from: Option<String>,
+    /// 收件人，多个以英文逗号分割, 环境变量 SMTP_TO
+    #[arg(long)]
+    pub smtp_to: Option<Vec<String>>,
+    /// 是否在邮件中附带 JSON 格式的报告, 环境变量 SMTP_ATTACH_REPORT true附带，false不附带，默认不附带
+    #[arg(long)]
+    pub smtp_attach_report: Option<bool>,
+    /// 通用 webhook 地址，通知事件以 JSON 发送到该地址, 环境变量 WEBHOOK_URL
+    #[arg(long)]
+    pub webhook_url: Option<Secret<String>>,
+    /// 通用 webhook 的请求方法，默认 POST, 环境变量 WEBHOOK_METHOD
+    #[arg(long)]
+    pub webhook_method: Option<String>,
+    /// 通用 webhook 的请求头，格式为 名称: 值，多个以英文逗号分割, 环境变量 WEBHOOK_HEADERS
+    #[arg(long)]
+    pub webhook_headers: Option<Vec<Secret<String>>>,
+    /// 通用 webhook 的请求体模板，{{event}} 为完整的事件 JSON，{{字段名}} 为事件字段的值，默认发送事件 JSON, 环境变量 WEBHOOK_BODY_TEMPLATE
+    #[arg(long)]
+    pub webhook_body_template: Option<String>,
+    /// 通用 webhook 的签名密钥，配置后请求头带上 X-Tc-Eo-Ssl-Timestamp 和 X-Tc-Eo-Ssl-Signature, 环境变量 WEBHOOK_SECRET
+    #[arg(long)]
+    pub webhook_secret: Option<Secret<String>>,
+    /// 通知使用的内置模板语言 zh、en，默认 zh, 环境变量 NOTIFY_LANGUAGE
+    #[arg(long)]
+    pub notify_language: Option<String>,
+    /// 自定义通知模板，渠道名称 -> 模板名称 -> 模板，只能在配置文件中设置
+    #[arg(skip)]
+    pub notify_templates: Option<HashMap<String, HashMap<String, String>>>,
+
+    /// 监听模式下文件最后一次变化后等待多久再部署，单位秒，默认10, 环境变量 TENCENTCLOUD_WATCH_DEBOUNCE
+    #[arg(long)]
+    pub watch_debounce: Option<u64>,
+    /// 常驻模式的 cron 表达式，支持 5 段或带秒的 6 段格式，默认 "0 3 * * *", 环境变量 TENCENTCLOUD_CRON
+    #[arg(long)]
+    pub cron: Option<String>,
+    /// 常驻模式每次执行增加的最大随机延迟，单位秒，默认300, 环境变量 TENCENTCLOUD_CRON_JITTER
+    #[arg(long)]
+    pub cron_jitter: Option<u64>,
+    /// 状态文件路径，记录每个任务最后一次成功部署的证书和部署前的证书，默认 tc-eo-ssl-state.json, 环境变量 TENCENTCLOUD_STATE_FILE
+    #[arg(long)]
+    pub state_file: Option<String>,
+
+    /// 日志级别 error、warn、info、debug、trace，trace 级别会输出签名过程，默认 info, 环境变量 TENCENTCLOUD_LOG_LEVEL
+    #[arg(long)]
+    pub log_level: Option<String>,
 }
 
+// 命令行参数未设置时从环境变量读取，默认值在使用处处理，以便配置文件中的值可以生效
 pub fn parse() -> anyhow::Result<Args> {
     let mut args = Args::parse();
-    if args.secret_id.is_none() {
-        match std::env::var("TENCENTCLOUD_SECRET_ID") {
-            Ok(s) => args.secret_id = Some(s),
-            Err(e) => println!("无法获取命令行参数 --secret-id 以及环境变量 TENCENTCLOUD_SECRET_ID: {}", e),
-        }
+    if args.config_file.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_CONFIG_FILE") {
+        args.config_file = Some(s);
     }
-    if args.secret_key.is_none() {
-        match std::env::var("TENCENTCLOUD_SECRET_KEY") {
-            Ok(s) => args.secret_key = Some(s),
-            Err(e) => println!("无法获取命令行参数 --secret-key 以及环境变量 TENCENTCLOUD_SECRET_KEY: {}", e),
-        }
+    if args.secret_id.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_SECRET_ID") {
+        args.secret_id = Some(Secret::new(s));
     }
-    if args.public_key_file_path.is_none() {
-        match std::env::var("TENCENTCLOUD_PUBLIC_KEY_FILE_PATH") {
-            Ok(s) => args.public_key_file_path = Some(s),
-            Err(e) => println!("无法获取命令行参数 --public-key-file-path 以及环境变量 TENCENTCLOUD_PUBLIC_KEY_FILE_PATH: {}", e),
-        }
+    if args.secret_key.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_SECRET_KEY") {
+        args.secret_key = Some(Secret::new(s));
     }
-    if args.private_key_file_path.is_none() {
-        match std::env::var("TENCENTCLOUD_PRIVATE_KEY_FILE_PATH") {
-            Ok(s) => args.private_key_file_path = Some(s),
-            Err(e) => println!("无法获取命令行参数 --private-key-file-path 以及环境变量 TENCENTCLOUD_PRIVATE_KEY_FILE_PATH: {}", e),
-        }
+    if args.token.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_TOKEN") {
+        args.token = Some(Secret::new(s));
     }
-    if args.instance_id_list.is_none() {
-        match std::env::var("TENCENTCLOUD_INSTANCE_ID_LIST") {
-            Ok(s) => args.instance_id_list = Some(s.split(",").map(|item| item.trim().to_string()).collect()),
-            Err(e) => println!("无法获取命令行参数 --instance-id-list 以及环境变量 TENCENTCLOUD_INSTANCE_ID_LIST: {}", e),
-        }
+    if args.profile.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_PROFILE") {
+        args.profile = Some(s);
+    }
+    if args.role_arn.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_ROLE_ARN") {
+        args.role_arn = Some(s);
+    }
+    if args.oidc_provider_id.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_OIDC_PROVIDER_ID") {
+        args.oidc_provider_id = Some(s);
+    }
+    if args.web_identity_token_file.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_WEB_IDENTITY_TOKEN_FILE") {
+        args.web_identity_token_file = Some(s);
+    }
+    if args.web_identity_token.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_WEB_IDENTITY_TOKEN") {
+        args.web_identity_token = Some(Secret::new(s));
+    }
+    if args.role_session_name.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_ROLE_SESSION_NAME") {
+        args.role_session_name = Some(s);
+    }
+    if args.public_key_file_path.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_PUBLIC_KEY_FILE_PATH") {
+        args.public_key_file_path = Some(s);
+    }
+    if args.private_key_file_path.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_PRIVATE_KEY_FILE_PATH") {
+        args.private_key_file_path = Some(s);
+    }
+    if args.ecc_public_key_file_path.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_ECC_PUBLIC_KEY_FILE_PATH") {
+        args.ecc_public_key_file_path = Some(s);
     }
-    if args.intl.is_none() {
-        match std::env::var("TENCENTCLOUD_INTL") {
-            Ok(s) => args.intl = Some(&s.to_lowercase() == "true"),
-            Err(_) => args.intl = Some(false),
+    if args.ecc_private_key_file_path.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_ECC_PRIVATE_KEY_FILE_PATH") {
+        args.ecc_private_key_file_path = Some(s);
+    }
+    if args.resource_type.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_RESOURCE_TYPE") {
+        args.resource_type = Some(s);
+    }
+    if args.region.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_REGION") {
+        args.region = Some(s);
+    }
+    if args.instance_id_list.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_INSTANCE_ID_LIST") {
+        args.instance_id_list = Some(s.split(",").map(|item| item.trim().to_string()).collect());
+    }
+    if args.auto_discover.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_AUTO_DISCOVER") {
+        args.auto_discover = Some(&s.to_lowercase() == "true");
+    }
+    if args.include_domains.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_INCLUDE_DOMAINS") {
+        args.include_domains = Some(s.split(",").map(|item| item.trim().to_string()).collect());
+    }
+    if args.exclude_domains.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_EXCLUDE_DOMAINS") {
+        args.exclude_domains = Some(s.split(",").map(|item| item.trim().to_string()).collect());
+    }
+    if args.deploy_backend.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_DEPLOY_BACKEND") {
+        args.deploy_backend = Some(s);
+    }
+    if args.eo_free_cert_fallback.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_EO_FREE_CERT_FALLBACK") {
+        args.eo_free_cert_fallback = Some(&s.to_lowercase() == "true");
+    }
+    if args.replace.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_REPLACE") {
+        args.replace = Some(&s.to_lowercase() == "true");
+    }
+    if args.replace_resource_types.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_REPLACE_RESOURCE_TYPES") {
+        args.replace_resource_types = Some(s.split(",").map(|item| item.trim().to_string()).collect());
+    }
+    if args.cleanup.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_CLEANUP") {
+        args.cleanup = Some(&s.to_lowercase() == "true");
+    }
+    if args.cleanup_keep.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_CLEANUP_KEEP") {
+        args.cleanup_keep = Some(s.parse::<usize>()?);
+    }
+    if args.intl.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_INTL") {
+        args.intl = Some(&s.to_lowercase() == "true");
+    }
+    if args.deploy_wait.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_DEPLOY_WAIT") {
+        args.deploy_wait = Some(&s.to_lowercase() == "true");
+    }
+    if args.deploy_wait_timeout.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_DEPLOY_WAIT_TIMEOUT") {
+        args.deploy_wait_timeout = Some(s.parse::<u64>()?);
+    }
+    if args.auto_rollback.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_AUTO_ROLLBACK") {
+        args.auto_rollback = Some(&s.to_lowercase() == "true");
+    }
+    if args.expiry_warning_days.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_EXPIRY_WARNING_DAYS") {
+        args.expiry_warning_days = Some(s.parse::<i64>()?);
+    }
+    if args.tg_bot_token.is_none() && let Ok(s) = std::env::var("TELEGRAM_BOT_TOKEN") {
+        args.tg_bot_token = Some(Secret::new(s));
+    }
+    if args.tg_chat_id.is_none() && let Ok(s) = std::env::var("TELEGRAM_CHAT_ID") {
+        args.tg_chat_id = Some(s.parse::<i64>()?);
+    }
+    if args.tg_topic_id.is_none() && let Ok(s) = std::env::var("TELEGRAM_TOPIC_ID") {
+        args.tg_topic_id = Some(s.parse::<i64>()?);
+    }
+    if args.tg_api_base_url.is_none() && let Ok(s) = std::env::var("TELEGRAM_API_BASE_URL") {
+        args.tg_api_base_url = Some(s);
+    }
+    if args.tg_silent_success.is_none() && let Ok(s) = std::env::var("TELEGRAM_SILENT_SUCCESS") {
+        args.tg_silent_success = Some(&s.to_lowercase() == "true");
+    }
+    if args.tg_command_chat_ids.is_none() && let Ok(s) = std::env::var("TELEGRAM_COMMAND_CHAT_IDS") {
+        args.tg_command_chat_ids = Some(s.split(",").map(|item| item.trim().parse::<i64>()).collect::<Result<Vec<i64>, _>>()?);
+    }
+    if args.wecom_webhook_url.is_none() && let Ok(s) = std::env::var("WECOM_WEBHOOK_URL") {
+        args.wecom_webhook_url = Some(Secret::new(s));
+    }
+    if args.dingtalk_webhook_url.is_none() && let Ok(s) = std::env::var("DINGTALK_WEBHOOK_URL") {
+        args.dingtalk_webhook_url = Some(Secret::new(s));
+    }
+    if args.dingtalk_secret.is_none() && let Ok(s) = std::env::var("DINGTALK_SECRET") {
+        args.dingtalk_secret = Some(Secret::new(s));
+    }
+    if args.feishu_webhook_url.is_none() && let Ok(s) = std::env::var("FEISHU_WEBHOOK_URL") {
+        args.feishu_webhook_url = Some(Secret::new(s));
+    }
+    if args.feishu_secret.is_none() && let Ok(s) = std::env::var("FEISHU_SECRET") {
+        args.feishu_secret = Some(Secret::new(s));
+    }
+    if args.smtp_host.is_none() && let Ok(s) = std::env::var("SMTP_HOST") {
+        args.smtp_host = Some(s);
+    }
+    if args.smtp_port.is_none() && let Ok(s) = std::env::var("SMTP_PORT") {
+        args.smtp_port = Some(s.parse::<u16>()?);
+    }
+    if args.smtp_security.is_none() && let Ok(s) = std::env::var("SMTP_SECURITY") {
+        args.smtp_security = Some(s);
+    }
+    if args.smtp_username.is_none() && let Ok(s) = std::env::var("SMTP_USERNAME") {
+        args.smtp_username = Some(s);
+    }
+    if args.smtp_password.is_none() && let Ok(s) = std::env::var("SMTP_PASSWORD") {
+        args.smtp_password = Some(Secret::new(s));
+    }
+    if args.smtp_from.is_none() && let Ok(s) = std::env::var("SMTP_FROM") {
+        args.smtp_from = Some(s);
+    }
+    if args.smtp_to.is_none() && let Ok(s) = std::env::var("SMTP_TO") {
+        args.smtp_to = Some(s.split(",").map(|item| item.trim().to_string()).collect());
+    }
+    if args.smtp_attach_report.is_none() && let Ok(s) = std::env::var("SMTP_ATTACH_REPORT") {
+        args.smtp_attach_report = Some(&s.to_lowercase() == "true");
+    }
+    if args.webhook_url.is_none() && let Ok(s) = std::env::var("WEBHOOK_URL") {
+        args.webhook_url = Some(Secret::new(s));
+    }
+    if args.webhook_method.is_none() && let Ok(s) = std::env::var("WEBHOOK_METHOD") {
+        args.webhook_method = Some(s);
+    }
+    if args.webhook_headers.is_none() && let Ok(s) = std::env::var("WEBHOOK_HEADERS") {
+        args.webhook_headers = Some(s.split(",").map(|item| Secret::new(item.trim().to_string())).collect());
+    }
+    if args.webhook_body_template.is_none() && let Ok(s) = std::env::var("WEBHOOK_BODY_TEMPLATE") {
+        args.webhook_body_template = Some(s);
+    }
+    if args.webhook_secret.is_none() && let Ok(s) = std::env::var("WEBHOOK_SECRET") {
+        args.webhook_secret = Some(Secret::new(s));
+    }
+    if args.notify_language.is_none() && let Ok(s) = std::env::var("NOTIFY_LANGUAGE") {
+        args.notify_language = Some(s);
+    }
+    if args.watch_debounce.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_WATCH_DEBOUNCE") {
+        args.watch_debounce = Some(s.parse::<u64>()?);
+    }
+    if args.cron.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_CRON") {
+        args.cron = Some(s);
+    }
+    if args.cron_jitter.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_CRON_JITTER") {
+        args.cron_jitter = Some(s.parse::<u64>()?);
+    }
+    if args.state_file.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_STATE_FILE") {
+        args.state_file = Some(s);
+    }
+    if args.log_level.is_none() && let Ok(s) = std::env::var("TENCENTCLOUD_LOG_LEVEL") {
+        args.log_level = Some(s);
+    }
+    anyhow::Ok(args)
+}
+
+impl Args {
+    // 用 other 中的值补全当前未设置的参数，当前已设置的值优先
+    pub fn or(self, other: &Args) -> Args {
+        Args {
+            command: self.command.or(other.command.clone()),
+            config_file: self.config_file.or(other.config_file.clone()),
+            secret_id: self.secret_id.or(other.secret_id.clone()),
+            secret_key: self.secret_key.or(other.secret_key.clone()),
+            token: self.token.or(other.token.clone()),
+            profile: self.profile.or(other.profile.clone()),
+            role_arn: self.role_arn.or(other.role_arn.clone()),
+            oidc_provider_id: self.oidc_provider_id.or(other.oidc_provider_id.clone()),
+            web_identity_token_file: self.web_identity_token_file.or(other.web_identity_token_file.clone()),
+            web_identity_token: self.web_identity_token.or(other.web_identity_token.clone()),
+            role_session_name: self.role_session_name.or(other.role_session_name.clone()),
+            public_key_file_path: self.public_key_file_path.or(other.public_key_file_path.clone()),
+            private_key_file_path: self.private_key_file_path.or(other.private_key_file_path.clone()),
+            ecc_public_key_file_path: self.ecc_public_key_file_path.or(other.ecc_public_key_file_path.clone()),
+            ecc_private_key_file_path: self.ecc_private_key_file_path.or(other.ecc_private_key_file_path.clone()),
+            resource_type: self.resource_type.or(other.resource_type.clone()),
+            region: self.region.or(other.region.clone()),
+            instance_id_list: self.instance_id_list.or(other.instance_id_list.clone()),
+            auto_discover: self.auto_discover.or(other.auto_discover),
+            include_domains: self.include_domains.or(other.include_domains.clone()),
+            exclude_domains: self.exclude_domains.or(other.exclude_domains.clone()),
+            deploy_backend: self.deploy_backend.or(other.deploy_backend.clone()),
+            eo_free_cert_fallback: self.eo_free_cert_fallback.or(other.eo_free_cert_fallback),
+            replace: self.replace.or(other.replace),
+            replace_resource_types: self.replace_resource_types.or(other.replace_resource_types.clone()),
+            cleanup: self.cleanup.or(other.cleanup),
+            cleanup_keep: self.cleanup_keep.or(other.cleanup_keep),
+            intl: self.intl.or(other.intl),
+            deploy_wait: self.deploy_wait.or(other.deploy_wait),
+            deploy_wait_timeout: self.deploy_wait_timeout.or(other.deploy_wait_timeout),
+            auto_rollback: self.auto_rollback.or(other.auto_rollback),
+            expiry_warning_days: self.expiry_warning_days.or(other.expiry_warning_days),
+            tg_bot_token: self.tg_bot_token.or(other.tg_bot_token.clone()),
+            tg_chat_id: self.tg_chat_id.or(other.tg_chat_id),
+            tg_topic_id: self.tg_topic_id.or(other.tg_topic_id),
+            tg_api_base_url: self.tg_api_base_url.or(other.tg_api_base_url.clone()),
+            tg_silent_success: self.tg_silent_success.or(other.tg_silent_success),
+            tg_command_chat_ids: self.tg_command_chat_ids.or(other.tg_command_chat_ids.clone()),
+            wecom_webhook_url: self.wecom_webhook_url.or(other.wecom_webhook_url.clone()),
+            dingtalk_webhook_url: self.dingtalk_webhook_url.or(other.dingtalk_webhook_url.clone()),
+            dingtalk_secret: self.dingtalk_secret.or(other.dingtalk_secret.clone()),
+            feishu_webhook_url: self.feishu_webhook_url.or(other.feishu_webhook_url.clone()),
+            feishu_secret: self.feishu_secret.or(other.feishu_secret.clone()),
+            smtp_host: self.smtp_host.or(other.smtp_host.clone()),
+            smtp_port: self.smtp_port.or(other.smtp_port),
+            smtp_security: self.smtp_security.or(other.smtp_security.clone()),
+            smtp_username: self.smtp_username.or(other.smtp_username.clone()),
+            smtp_password: self.smtp_password.or(other.smtp_password.clone()),
+            smtp_from: self.smtp_from.or(other.smtp_from.clone()),
+            smtp_to: self.smtp_to.or(other.smtp_to.clone()),
+            smtp_attach_report: self.smtp_attach_report.or(other.smtp_attach_report),
+            webhook_url: self.webhook_url.or(other.webhook_url.clone()),
+            webhook_method: self.webhook_method.or(other.webhook_method.clone()),
+            webhook_headers: self.webhook_headers.or(other.webhook_headers.clone()),
+            webhook_body_template: self.webhook_body_template.or(other.webhook_body_template.clone()),
+            webhook_secret: self.webhook_secret.or(other.webhook_secret.clone()),
+            notify_language: self.notify_language.or(other.notify_language.clone()),
+            notify_templates: self.notify_templates.or(other.notify_templates.clone()),
+            watch_debounce: self.watch_debounce.or(other.watch_debounce),
+            cron: self.cron.or(other.cron.clone()),
+            cron_jitter: self.cron_jitter.or(other.cron_jitter),
+            state_file: self.state_file.or(other.state_file.clone()),
+            log_level: self.log_level.or(other.log_level.clone()),
         }
     }
-    if args.tg_bot_token.is_none() {
-        match std::env::var("TELEGRAM_BOT_TOKEN") {
-            Ok(s) => args.tg_bot_token = Some(s),
-            Err(_) => (),
+
+    // 是否同时部署 RSA 和 ECC 证书
+    pub fn is_dual(&self) -> bool {
+        self.ecc_public_key_file_path.is_some()
+    }
+
+    // 部署方式，未设置时同时部署 RSA 和 ECC 证书使用 teo，否则使用 ssl
+    pub fn backend(&self) -> &str {
+        match &self.deploy_backend {
+            Some(deploy_backend) => deploy_backend,
+            None if self.is_dual() => "teo",
+            None => "ssl",
         }
     }
-    if args.tg_chat_id.is_none() {
-        match std::env::var("TELEGRAM_CHAT_ID") {
-            Ok(s) => args.tg_chat_id = Some(s.parse::<i64>()?),
-            Err(_) => (),
+
+    // 部署的资源类型，未设置或无法解析时为 teo，部署前已经由 check_deploy 检查过
+    pub fn resource(&self) -> ResourceType {
+        self.resource_type.as_deref().and_then(|resource_type| resource_type.parse().ok()).unwrap_or(ResourceType::Teo)
+    }
+
+    // 一键更新的资源类型
+    pub fn replace_resource_types(&self) -> Vec<String> {
+        self.replace_resource_types.clone().unwrap_or_else(|| vec![self.resource_type.clone().unwrap_or("teo".to_string())])
+    }
+
+    // 检查证书文件参数
+    pub fn check_certificate_files(&self) -> anyhow::Result<()> {
+        if self.public_key_file_path.is_none() {
+            return Err(anyhow::anyhow!("无法获取命令行参数 --public-key-file-path、环境变量 TENCENTCLOUD_PUBLIC_KEY_FILE_PATH 以及配置文件中的 public_key_file_path"));
         }
+        if self.private_key_file_path.is_none() {
+            return Err(anyhow::anyhow!("无法获取命令行参数 --private-key-file-path、环境变量 TENCENTCLOUD_PRIVATE_KEY_FILE_PATH 以及配置文件中的 private_key_file_path"));
+        }
+        if self.ecc_public_key_file_path.is_some() != self.ecc_private_key_file_path.is_some() {
+            return Err(anyhow::anyhow!("ECC 公钥文件路径和私钥文件路径需要同时设置"));
+        }
+        anyhow::Ok(())
     }
-    if args.tg_topic_id.is_none() {
-        match std::env::var("TELEGRAM_TOPIC_ID") {
-            Ok(s) => args.tg_topic_id = Some(s.parse::<i64>()?),
-            Err(_) => (),
+
+    // 检查部署参数，开启自动发现时可以不设置域名列表
+    pub fn check_deploy(&self) -> anyhow::Result<()> {
+        if let Some(deploy_backend) = &self.deploy_backend
+            && deploy_backend != "ssl" && deploy_backend != "teo"
+        {
+            return Err(anyhow::anyhow!("不支持的部署方式 {}，可选 ssl、teo", deploy_backend));
+        }
+        // 一键更新不需要域名列表，也不使用部署方式
+        if self.replace.unwrap_or(false) {
+            for resource_type in self.replace_resource_types() {
+                let resource_type = resource_type.parse::<ResourceType>()?;
+                if resource_type.requires_region() && self.region.is_none() {
+                    return Err(anyhow::anyhow!("一键更新 {} 资源需要设置命令行参数 --region、环境变量 TENCENTCLOUD_REGION 或配置文件中的 region", resource_type.as_str()));
+                }
+            }
+            return anyhow::Ok(());
         }
+        if self.is_dual() && self.backend() != "teo" {
+            return Err(anyhow::anyhow!("同时部署 RSA 和 ECC 证书需要使用 teo 部署方式"));
+        }
+        let resource_type = self.resource_type.as_deref().unwrap_or("teo").parse::<ResourceType>()?;
+        if resource_type != ResourceType::Teo && (self.backend() == "teo" || self.auto_discover.unwrap_or(false)) {
+            return Err(anyhow::anyhow!("teo 部署方式、双证书和自动发现域名只支持 teo 资源类型"));
+        }
+        if resource_type.requires_region() && self.region.is_none() {
+            return Err(anyhow::anyhow!("{} 资源需要设置命令行参数 --region、环境变量 TENCENTCLOUD_REGION 或配置文件中的 region", resource_type.as_str()));
+        }
+        for instance_id in self.instance_id_list.iter().flatten() {
+            resource_type.check_instance_id(instance_id)?;
+        }
+        if self.instance_id_list.is_none() && !self.auto_discover.unwrap_or(false) {
+            return Err(anyhow::anyhow!("无法获取命令行参数 --instance-id-list、环境变量 TENCENTCLOUD_INSTANCE_ID_LIST 以及配置文件中的 instance_id_list，也没有开启 --auto-discover"));
+        }
+        anyhow::Ok(())
     }
-    anyhow::Ok(args)
-}
\ No newline at end of file
+
+    // 检查上传部署需要的全部参数，密钥可以从其他来源获取，不在这里检查
+    pub fn check(&self) -> anyhow::Result<()> {
+        self.check_certificate_files()?;
+        self.check_deploy()
+    }
+}
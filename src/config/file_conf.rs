@@ -0,0 +1,132 @@
+use serde::Deserialize;
+
+use super::args_conf::Args;
+
+// 配置文件，顶层的参数作为所有任务的默认值，每个 [[jobs]] 声明一个部署任务
+#[derive(Deserialize)]
+pub struct FileConfig {
+    #[serde(flatten)]
+    pub defaults: Args,
+    #[serde(default)]
+    pub jobs: Vec<JobConfig>,
+}
+
+#[derive(Deserialize)]
+pub struct JobConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub args: Args,
+}
+
+// 合并命令行、环境变量和配置文件后的部署任务
+#[derive(Clone)]
+pub struct Job {
+    pub name: String,
+    pub args: Args,
+}
+
+pub fn load(path: &str) -> anyhow::Result<FileConfig> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("读取配置文件 {} 失败: {}", path, e))?;
+    toml::from_str::<FileConfig>(&content)
+        .map_err(|e| anyhow::anyhow!("解析配置文件 {} 失败: {}", path, e))
+}
+
+// 生成部署任务列表，优先级：命令行参数 > 环境变量 > 任务配置 > 配置文件顶层配置
+pub fn jobs(args: Args) -> anyhow::Result<Vec<Job>> {
+    let jobs = match &args.config_file {
+        None => vec![Job { name: "default".to_string(), args }],
+        Some(path) => merge(args.clone(), load(path)?),
+    };
+    anyhow::Ok(jobs)
+}
+
+// 不针对具体任务的参数，如查询、删除证书时使用，优先级：命令行参数 > 环境变量 > 配置文件顶层配置
+pub fn defaults(args: Args) -> anyhow::Result<Args> {
+    match &args.config_file {
+        None => anyhow::Ok(args),
+        Some(path) => anyhow::Ok(args.clone().or(&load(path)?.defaults)),
+    }
+}
+
+// 检查每个任务的参数
+pub fn check(jobs: &[Job], check: impl Fn(&Args) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    for job in jobs {
+        check(&job.args).map_err(|e| anyhow::anyhow!("任务 {} 参数错误: {}", job.name, e))?;
+    }
+    anyhow::Ok(())
+}
+
+fn merge(args: Args, file: FileConfig) -> Vec<Job> {
+    if file.jobs.is_empty() {
+        return vec![Job { name: "default".to_string(), args: args.or(&file.defaults) }];
+    }
+    file.jobs
+        .into_iter()
+        .map(|job| Job {
+            name: job.name,
+            args: args.clone().or(&job.args).or(&file.defaults),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::secret::Secret;
+
+    #[test]
+    fn test_merge() {
+        let file = toml::from_str::<FileConfig>(r#"
+            secret_id = "file_id"
+            secret_key = "file_key"
+            intl = true
+
+            [[jobs]]
+            name = "a"
+            public_key_file_path = "/ssl/a.pem"
+            private_key_file_path = "/ssl/a.key"
+            instance_id_list = ["a.example.com"]
+            tg_chat_id = 1
+
+            [[jobs]]
+            name = "b"
+            public_key_file_path = "/ssl/b.pem"
+            private_key_file_path = "/ssl/b.key"
+            instance_id_list = ["b.example.com", "*.b.example.com"]
+            intl = false
+        "#).unwrap();
+        let args = Args { secret_key: Some(Secret::new("cli_key".to_string())), ..Default::default() };
+
+        let jobs = merge(args, file);
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "a");
+        assert_eq!(jobs[0].args.secret_id.as_ref().map(|secret| secret.expose().as_str()), Some("file_id"));
+        assert_eq!(jobs[0].args.secret_key.as_ref().map(|secret| secret.expose().as_str()), Some("cli_key"));
+        assert_eq!(jobs[0].args.intl, Some(true));
+        assert_eq!(jobs[0].args.tg_chat_id, Some(1));
+        assert_eq!(jobs[1].args.intl, Some(false));
+        assert_eq!(jobs[1].args.instance_id_list, Some(vec!["b.example.com".to_string(), "*.b.example.com".to_string()]));
+        assert!(jobs.iter().all(|job| job.args.check().is_ok()));
+    }
+
+    #[test]
+    fn test_notify_templates() {
+        let file = toml::from_str::<FileConfig>(r#"
+            notify_language = "en"
+
+            [notify_templates.wecom]
+            deploy_success = """
+            {{job}} deployed
+            Domains: {{domains}}"""
+
+            [[jobs]]
+            name = "a"
+        "#).unwrap();
+
+        let jobs = merge(Args::default(), file);
+        let templates = jobs[0].args.notify_templates.as_ref().unwrap();
+        assert_eq!(templates["wecom"]["deploy_success"].trim(), "{{job}} deployed\n            Domains: {{domains}}");
+        assert_eq!(jobs[0].args.notify_language.as_deref(), Some("en"));
+    }
+}
@@ -0,0 +1,19 @@
+use tracing_subscriber::EnvFilter;
+
+// 初始化日志，级别可以是 error、warn、info、debug、trace，只作用于本程序，依赖库只输出 warn 以上
+// 也可以直接传 tracing 的过滤表达式，如 "trace,hyper=debug"
+// 签名过程的中间结果只在 trace 级别输出，密钥等敏感值在任何级别都不会输出
+// 日志输出到 stderr，stdout 只输出子命令的结果，便于脚本处理
+pub fn init(level: Option<&str>) {
+    let level = level.unwrap_or("info");
+    let directive = match level {
+        "error" | "warn" | "info" | "debug" | "trace" => format!("warn,tc_eo_ssl={}", level),
+        _ => level.to_string(),
+    };
+    let filter = EnvFilter::try_new(directive).unwrap_or_else(|_| EnvFilter::new("warn,tc_eo_ssl=info"));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_writer(std::io::stderr)
+        .init();
+}
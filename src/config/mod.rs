@@ -1 +1,3 @@
-pub mod args_conf;
\ No newline at end of file
+pub mod args_conf;
+pub mod file_conf;
+pub mod log_conf;
\ No newline at end of file
@@ -1,200 +1,1273 @@
-use serde::{Deserialize, Serialize};
-use service::tc_request::{TencentCloudRequest, TencentCloudResponse};
+use std::collections::HashMap;
+
+use service::credential::CredentialProvider;
+use service::notify::Event;
+use service::ssl_api::*;
+use service::tc_request::TencentCloudResponse;
+
+use config::args_conf::{Args, Command};
 
 mod config;
 mod service;
 
-// 上传证书成功响应数据结构
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UploadCertificateData {
-    #[serde(rename = "CertificateId")]
-    pub certificate_id: String,
+const DEFAULT_STATE_FILE: &str = "tc-eo-ssl-state.json";
+
+// 一次上传部署的结果
+#[derive(Debug, Clone)]
+pub struct DeployResult {
+    pub fingerprint: String,
+    // RSA 证书在前，同时部署 ECC 证书时第二个是 ECC 证书
+    pub certificate_ids: Vec<String>,
+    pub deploy_record_id: Option<i64>,
+    pub instance_id_list: Vec<String>,
 }
 
-// 部署证书成功响应数据结构
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DeployCertificateData {
-    #[serde(rename = "DeployRecordId")]
-    pub deploy_record_id: i64,
-    #[serde(rename = "DeployStatus")]
-    pub deploy_status: i32,
+// 在已上传的证书中查找指纹相同的证书，找到则返回其 CertificateId
+async fn find_uploaded_certificate(
+    credentials: &CredentialProvider,
+    certificate_public_key: &str,
+    fingerprint: &str,
+    host: &str,
+) -> anyhow::Result<Option<String>> {
+    let leaf = service::cert::leaf(certificate_public_key)?;
+    let Some(domain) = service::cert::primary_domain(&leaf) else {
+        return Ok(None);
+    };
+
+    let mut domains = service::cert::dns_names(&leaf);
+    domains.push(domain.clone());
+    let not_after = service::cert::not_after(&leaf)?;
+
+    let mut filter = CertificateFilter {
+        search_key: Some(domain),
+        upload_only: true,
+        ..Default::default()
+    };
+    loop {
+        let describe_param = describe_certificates(
+            &credentials.get().await?,
+            &filter,
+            host.to_string(),
+        ).await?;
+        let describe_response = serde_json::from_str::<TencentCloudResponse<DescribeCertificatesData>>(&describe_param)?;
+        if let Some(error) = describe_response.response.error {
+            return Err(error.with_request_id(&describe_response.response.request_id).into());
+        }
+        let Some(data) = describe_response.response.data else {
+            return Ok(None);
+        };
+        let count = data.certificates.len() as i64;
+
+        // 域名或到期时间不同的证书不可能指纹相同，只查询剩下的证书的详情
+        for certificate in data.certificates.into_iter().filter(|certificate| certificate.may_match(&domains, not_after)) {
+            let detail_param = describe_certificate_detail(
+                &credentials.get().await?,
+                certificate.certificate_id.clone(),
+                host.to_string(),
+            ).await?;
+            let detail_response = serde_json::from_str::<TencentCloudResponse<DescribeCertificateDetailData>>(&detail_param)?;
+            if let Some(error) = detail_response.response.error {
+                return Err(error.with_request_id(&detail_response.response.request_id).into());
+            }
+            let Some(public_key) = detail_response.response.data.and_then(|data| data.certificate_public_key) else {
+                continue;
+            };
+            let Ok(remote_leaf) = service::cert::leaf(&public_key) else {
+                continue;
+            };
+            if service::cert::fingerprint(&remote_leaf)? == fingerprint {
+                return Ok(Some(certificate.certificate_id));
+            }
+        }
+
+        filter.offset += count;
+        if count == 0 || filter.offset >= data.total_count {
+            return Ok(None);
+        }
+    }
 }
 
-// 修改是否忽略证书到期通知成功响应数据结构
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ModifyCertificateNotificationData {
-    #[serde(rename = "CertificateIds")]
-    pub certificate_ids: Vec<String>,
+// 查询部署记录的全部详情，资源超过一页时分页查询
+async fn deploy_record_details(
+    credentials: &CredentialProvider,
+    deploy_record_id: i64,
+    host: &str,
+) -> anyhow::Result<Vec<DeployRecordDetail>> {
+    all_pages(async |offset| {
+        let detail_param = describe_host_deploy_record_detail(
+            &credentials.get().await?,
+            deploy_record_id.to_string(),
+            offset,
+            host.to_string(),
+        ).await?;
+        DescribeHostDeployRecordDetailData::page(&detail_param)
+    }).await
 }
 
-// 上传证书
-pub async fn upload_certificate(
-    secret_id: String,
-    secret_key: String,
-    certificate_public_key: String,
-    certificate_private_key: String,
-    host: String,
-) -> anyhow::Result<String> {
-    let payload = serde_json::json!({
-        "CertificatePublicKey": certificate_public_key,
-        "CertificatePrivateKey": certificate_private_key,
-        "CertificateUse": "teo"
-    })
-    .to_string();
+// 轮询部署记录，直到所有域名部署结束或超时，所有域名部署成功才返回 Ok
+async fn wait_deploy_record(
+    credentials: &CredentialProvider,
+    deploy_record_id: i64,
+    instance_id_list: &[String],
+    timeout: std::time::Duration,
+    host: &str,
+) -> anyhow::Result<()> {
+    let interval = std::time::Duration::from_secs(10);
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let details = deploy_record_details(credentials, deploy_record_id, host).await?;
 
-    let request = TencentCloudRequest::new(
-        secret_id,
-        secret_key,
-        "ssl".to_string(),
-        host,
-        "".to_string(),
-        "UploadCertificate".to_string(),
-        "2019-12-05".to_string(),
-        payload,
-        "".to_string(),
-    );
-
-    request.send().await
-}
-
-// 部署证书
-pub async fn deploy_certificate(
-    secret_id: String,
-    secret_key: String,
-    certificate_id: String,
-    instance_id_list: Vec<String>,
-    host: String,
-) -> anyhow::Result<String> {
-    let payload = serde_json::json!({
-        "CertificateId": certificate_id,
-        "InstanceIdList": instance_id_list,
-        "ResourceType": "teo"
-    })
-    .to_string();
+        if !details.is_empty() && details.iter().all(|detail| detail.is_finished()) {
+            let mut failed = vec![];
+            for detail in &details {
+                tracing::info!("部署结果 {:?}: {} {}", detail.names(), detail.status_text(), detail.error_msg.as_deref().unwrap_or(""));
+                if detail.status != 1 {
+                    failed.push(format!("{:?} {} {}", detail.names(), detail.status_text(), detail.error_msg.as_deref().unwrap_or("")));
+                }
+            }
+            let recorded: Vec<String> = details.iter().flat_map(|detail| detail.names()).collect();
+            for instance_id in instance_id_list {
+                if !recorded.contains(instance_id) {
+                    failed.push(format!("{} 没有部署记录", instance_id));
+                }
+            }
+            if !failed.is_empty() {
+                return Err(anyhow::anyhow!("部署未全部成功: {}", failed.join("; ")));
+            }
+            return Ok(());
+        }
 
-    let request = TencentCloudRequest::new(
-        secret_id.to_string(),
-        secret_key.to_string(),
-        "ssl".to_string(),
-        host,
-        "".to_string(),
-        "DeployCertificateInstance".to_string(),
-        "2019-12-05".to_string(),
-        payload,
-        "".to_string(),
-    );
-
-    request.send().await
-}
-
-// 修改是否忽略证书到期通知
-pub async fn modify_certificate_notification(
-    secret_id: String,
-    secret_key: String,
-    certificate_id: String,
-    host: String,
-) -> anyhow::Result<String> {
-    let payload = serde_json::json!({
-        "CertificateIds": [certificate_id],
-        "SwitchStatus": 1
-    })
-    .to_string();
+        if std::time::Instant::now() + interval > deadline {
+            let pending: Vec<String> = details.iter().filter(|detail| !detail.is_finished()).map(|detail| format!("{:?} {}", detail.names(), detail.status_text())).collect();
+            return Err(anyhow::anyhow!("等待部署完成超时，DeployRecordId: {}，未完成: {}", deploy_record_id, pending.join("; ")));
+        }
+        tracing::info!("部署进行中，{} 秒后再次查询...", interval.as_secs());
+        tokio::time::sleep(interval).await;
+    }
+}
 
-    let request = TencentCloudRequest::new(
-        secret_id.to_string(),
-        secret_key.to_string(),
-        "ssl".to_string(),
-        host,
-        "".to_string(),
-        "ModifyCertificatesExpiringNotificationSwitch".to_string(),
-        "2019-12-05".to_string(),
-        payload,
-        "".to_string(),
-    );
+// 查询部署记录中各域名部署前绑定的证书，部署记录刚创建时可能还没有详情，最多重试几次
+async fn previous_certificate_ids(
+    credentials: &CredentialProvider,
+    deploy_record_id: i64,
+    instance_id_list: &[String],
+    host: &str,
+) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    for _ in 0..5 {
+        let details = deploy_record_details(credentials, deploy_record_id, host).await?;
+        if !details.is_empty() {
+            let mut previous = HashMap::new();
+            for detail in details {
+                let Some(old_cert_id) = detail.old_cert_id.clone().filter(|id| !id.is_empty()) else {
+                    continue;
+                };
+                for name in detail.names().into_iter().filter(|name| instance_id_list.contains(name)) {
+                    previous.insert(name, vec![old_cert_id.clone()]);
+                }
+            }
+            return Ok(previous);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+    Ok(HashMap::new())
+}
 
-    request.send().await
+// 把域名重新部署到之前绑定的证书，teo 部署方式通过 ModifyHostsCertificate 恢复域名绑定的全部证书，
+// ssl 部署方式按证书分组部署，配置了等待部署完成时等待回滚完成
+async fn rollback(
+    args: &config::args_conf::Args,
+    credentials: &CredentialProvider,
+    previous: &HashMap<String, Vec<String>>,
+    host: &str,
+) -> anyhow::Result<()> {
+    if previous.is_empty() {
+        return Err(anyhow::anyhow!("没有部署前的证书记录，无法回滚"));
+    }
+    if args.backend() == "teo" {
+        let host = service::teo_api::teo_host(args.intl.unwrap_or(false));
+        return service::eo_deploy::rollback(credentials, previous, &host).await;
+    }
+    let mut groups: HashMap<&String, Vec<String>> = HashMap::new();
+    for (domain, certificate_ids) in previous {
+        for certificate_id in certificate_ids {
+            groups.entry(certificate_id).or_default().push(domain.clone());
+        }
+    }
+    let mut errors = vec![];
+    for (certificate_id, domains) in groups {
+        tracing::info!("正在回滚 {:?} 到证书 {}...", domains, certificate_id);
+        let deploy_param = deploy_certificate(
+            &credentials.get().await?,
+            certificate_id.clone(),
+            domains.clone(),
+            args.resource().as_str(),
+            args.region.clone().unwrap_or_default(),
+            host.to_string(),
+        ).await?;
+        let deploy_response = serde_json::from_str::<TencentCloudResponse<DeployCertificateData>>(&deploy_param)?;
+        if let Some(error) = deploy_response.response.error {
+            errors.push(format!("{:?} -> {}: {}", domains, certificate_id, error));
+            continue;
+        }
+        let deploy_record_id = deploy_response.response.data.unwrap().deploy_record_id;
+        tracing::info!("回滚部署任务已创建，DeployRecordId: {}", deploy_record_id);
+        if let Some(timeout) = wait_timeout(args)
+            && let Err(e) = wait_deploy_record(credentials, deploy_record_id, &domains, timeout, host).await
+        {
+            errors.push(format!("{:?} -> {}: {}", domains, certificate_id, e));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("回滚失败: {}", errors.join("; ")));
+    }
+    Ok(())
 }
 
-async fn deploy(args: &config::args_conf::Args) -> anyhow::Result<()> {
-    let certificate_public_key = std::fs::read_to_string(args.public_key_file_path.as_ref().unwrap())?;
-    let certificate_private_key = std::fs::read_to_string(args.private_key_file_path.as_ref().unwrap())?;
-    let secret_id = args.secret_id.as_ref().unwrap();
-    let secret_key = args.secret_key.as_ref().unwrap();
-    let instance_id_list = args.instance_id_list.as_ref().unwrap();
-    let intl = args.intl.unwrap_or(false);
-    let host = if intl { "ssl.intl.tencentcloudapi.com".to_string() } else { "ssl.tencentcloudapi.com".to_string() };
+fn ssl_host(intl: bool) -> String {
+    if intl { "ssl.intl.tencentcloudapi.com".to_string() } else { "ssl.tencentcloudapi.com".to_string() }
+}
+
+fn wait_timeout(args: &config::args_conf::Args) -> Option<std::time::Duration> {
+    if args.deploy_wait.unwrap_or(false) {
+        Some(std::time::Duration::from_secs(args.deploy_wait_timeout.unwrap_or(600)))
+    } else {
+        None
+    }
+}
 
-    // 1. 上传证书
-    println!("正在上传证书 {:?} {:?} ...", args.public_key_file_path, args.private_key_file_path);
+// 读取并校验一对证书和私钥，返回证书、私钥和证书指纹，私钥在使用完后清零内存
+fn read_pair(
+    public_key_file_path: &str,
+    private_key_file_path: &str,
+    instance_id_list: &[String],
+) -> anyhow::Result<(String, zeroize::Zeroizing<String>, String)> {
+    let certificate_public_key = std::fs::read_to_string(public_key_file_path)?;
+    let certificate_private_key = zeroize::Zeroizing::new(std::fs::read_to_string(private_key_file_path)?);
+
+    tracing::info!("正在校验本地证书 {}...", public_key_file_path);
+    service::cert::validate(&certificate_public_key, &certificate_private_key, instance_id_list)?;
+    tracing::info!("本地证书校验通过");
+    let fingerprint = service::cert::fingerprint(&service::cert::leaf(&certificate_public_key)?)?;
+    tracing::info!("本地证书指纹 SHA-256: {}", fingerprint);
+    Ok((certificate_public_key, certificate_private_key, fingerprint))
+}
+
+// 上传证书，已上传过相同指纹的证书时直接复用，返回 CertificateId
+async fn upload_pair(
+    credentials: &CredentialProvider,
+    certificate_public_key: String,
+    certificate_private_key: &str,
+    fingerprint: &str,
+    certificate_use: Option<&str>,
+    host: &str,
+) -> anyhow::Result<String> {
+    tracing::info!("正在查找已上传的相同证书...");
+    let existing_certificate_id = find_uploaded_certificate(
+        credentials,
+        &certificate_public_key,
+        fingerprint,
+        host,
+    ).await?;
+    if let Some(certificate_id) = existing_certificate_id {
+        tracing::info!("证书已存在，跳过上传，CertificateId: {}", certificate_id);
+        return Ok(certificate_id);
+    }
+    tracing::info!("正在上传证书 {}...", fingerprint);
     let upload_param = upload_certificate(
-        secret_id.to_string(),
-        secret_key.to_string(),
+        &credentials.get().await?,
         certificate_public_key,
         certificate_private_key,
-        host.clone(),
+        certificate_use,
+        host.to_string(),
     ).await?;
     let upload_response = serde_json::from_str::<TencentCloudResponse<UploadCertificateData>>(&upload_param)?;
-    if upload_response.response.error.is_some() {
-        println!("证书上传失败");
-        return Err(anyhow::anyhow!(upload_response.response.error.unwrap().to_string()));
+    if let Some(error) = upload_response.response.error {
+        tracing::error!("证书上传失败");
+        return Err(error.with_request_id(&upload_response.response.request_id).into());
     }
     let certificate_id = upload_response.response.data.unwrap().certificate_id;
-    println!("证书上传成功，CertificateId: {}", certificate_id);
+    tracing::info!("证书上传成功，CertificateId: {}", certificate_id);
+    Ok(certificate_id)
+}
+
+// 校验并上传任务的证书，配置了 ECC 证书时两张证书都校验通过后再一起上传，
+// 返回证书指纹（多张证书以英文逗号连接）和 CertificateId 列表，RSA 证书在前
+async fn upload(args: &config::args_conf::Args, credentials: &CredentialProvider, host: &str) -> anyhow::Result<(String, Vec<String>)> {
+    // 实例ID中的域名需要被证书覆盖
+    let resource_type = args.resource();
+    let domains: Vec<String> = args.instance_id_list.iter().flatten()
+        .filter_map(|instance_id| resource_type.domain(instance_id))
+        .map(|domain| domain.to_string())
+        .collect();
+
+    // 0. 本地校验证书
+    let mut pairs = vec![read_pair(
+        args.public_key_file_path.as_ref().unwrap(),
+        args.private_key_file_path.as_ref().unwrap(),
+        &domains,
+    )?];
+    if let (Some(ecc_public_key_file_path), Some(ecc_private_key_file_path)) = (&args.ecc_public_key_file_path, &args.ecc_private_key_file_path) {
+        pairs.push(read_pair(ecc_public_key_file_path, ecc_private_key_file_path, &domains)?);
+        service::cert::validate_dual(&pairs[0].0, &pairs[1].0)?;
+    }
+
+    // 1. 上传证书，已存在相同指纹的证书时直接复用
+    let mut fingerprints = vec![];
+    let mut certificate_ids = vec![];
+    for (certificate_public_key, certificate_private_key, fingerprint) in pairs {
+        certificate_ids.push(upload_pair(credentials, certificate_public_key, &certificate_private_key, &fingerprint, resource_type.certificate_use(), host).await?);
+        fingerprints.push(fingerprint);
+    }
+    Ok((fingerprints.join(","), certificate_ids))
+}
+
+// 任务本地证书的指纹，与 upload 返回的格式相同
+fn local_fingerprint(args: &config::args_conf::Args) -> anyhow::Result<String> {
+    let mut fingerprints = vec![service::cert::file_fingerprint(args.public_key_file_path.as_ref().unwrap())?];
+    if let Some(ecc_public_key_file_path) = &args.ecc_public_key_file_path {
+        fingerprints.push(service::cert::file_fingerprint(ecc_public_key_file_path)?);
+    }
+    Ok(fingerprints.join(","))
+}
+
+// 本地的 RSA 证书，读取失败时为空
+fn local_certificate(args: &config::args_conf::Args) -> Option<openssl::x509::X509> {
+    let pem = std::fs::read_to_string(args.public_key_file_path.as_ref()?).ok()?;
+    service::cert::leaf(&pem).ok()
+}
+
+// teo 部署方式，按站点配置证书，记录部署前的证书
+async fn deploy_to_hosts(
+    job: &config::file_conf::Job,
+    credentials: &CredentialProvider,
+    certificate_ids: &[String],
+    state_file: &str,
+) -> anyhow::Result<()> {
+    let args = &job.args;
+    let host = service::teo_api::teo_host(args.intl.unwrap_or(false));
+    let zones = service::eo_deploy::resolve_zones(credentials, args.instance_id_list.as_ref().unwrap(), &host).await?;
+    let previous = service::eo_deploy::previous_certificate_ids(&zones);
+    tracing::info!("部署前各域名绑定的证书: {:?}", previous);
+    if let Err(e) = record_previous(state_file, &job.name, &previous, certificate_ids) {
+        tracing::warn!("记录部署前的证书失败: {}", e);
+    }
+    service::eo_deploy::deploy(credentials, &zones, certificate_ids, args.eo_free_cert_fallback.unwrap_or(false), &host).await
+}
+
+// 把证书部署到任务的域名，记录部署前的证书，需要时等待部署完成并在失败时自动回滚，返回 DeployRecordId，teo 部署方式没有部署记录
+async fn deploy_to_instances(
+    job: &config::file_conf::Job,
+    credentials: &CredentialProvider,
+    certificate_ids: &[String],
+    host: &str,
+    state_file: &str,
+) -> anyhow::Result<Option<i64>> {
+    let args = &job.args;
+    if args.backend() == "teo" {
+        deploy_to_hosts(job, credentials, certificate_ids, state_file).await?;
+        return Ok(None);
+    }
+    let [certificate_id] = certificate_ids else {
+        return Err(anyhow::anyhow!("ssl 部署方式一次只能部署一个证书，同时部署多个证书请使用 teo 部署方式"));
+    };
+    let resource_type = args.resource();
+    let instance_id_list = args.instance_id_list.as_ref().unwrap();
+    // 部署记录按域名记录，实例ID不是域名的资源无法按实例核对部署结果和记录部署前的证书
+    let recorded_instance_id_list: &[String] = if resource_type.is_domain() { instance_id_list } else { &[] };
 
     // 2. 部署证书
-    println!("正在部署证书 {} 到 {:?}...", certificate_id, args.instance_id_list);
+    tracing::info!("正在部署证书 {} 到 {} {:?}...", certificate_id, resource_type.as_str(), args.instance_id_list);
     let deploy_param = deploy_certificate(
-        secret_id.to_string(),
-        secret_key.to_string(),
+        &credentials.get().await?,
         certificate_id.to_string(),
         instance_id_list.to_vec(),
-        host.clone(),
+        resource_type.as_str(),
+        args.region.clone().unwrap_or_default(),
+        host.to_string(),
     ).await?;
     let deploy_response = serde_json::from_str::<TencentCloudResponse<DeployCertificateData>>(&deploy_param)?;
-    if deploy_response.response.error.is_some() {
-        println!("证书部署失败");
-        return Err(anyhow::anyhow!(deploy_response.response.error.unwrap().to_string()));
+    if let Some(error) = deploy_response.response.error {
+        tracing::error!("证书部署失败");
+        return Err(error.with_request_id(&deploy_response.response.request_id).into());
     }
     let deploy_record_id = deploy_response.response.data.unwrap().deploy_record_id;
-    println!("证书部署成功，DeployRecordId: {}", deploy_record_id);
+    let mut previous = match previous_certificate_ids(credentials, deploy_record_id, recorded_instance_id_list, host).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            tracing::warn!("查询部署前的证书失败: {}", e);
+            HashMap::new()
+        },
+    };
+    tracing::info!("部署前各域名绑定的证书: {:?}", previous);
+    if let Err(e) = record_previous(state_file, &job.name, &previous, certificate_ids) {
+        tracing::warn!("记录部署前的证书失败: {}", e);
+    }
+    // 部署前已经绑定本次证书的域名不需要回滚
+    previous.retain(|_, bound| !bound.contains(certificate_id));
+    if let Some(timeout) = wait_timeout(args) {
+        tracing::info!("证书部署任务已创建，DeployRecordId: {}，等待部署完成...", deploy_record_id);
+        if let Err(e) = wait_deploy_record(credentials, deploy_record_id, recorded_instance_id_list, timeout, host).await {
+            if !args.auto_rollback.unwrap_or(false) {
+                return Err(e);
+            }
+            tracing::warn!("部署失败，正在自动回滚: {}", e);
+            return match rollback(args, credentials, &previous, host).await {
+                Ok(_) => Err(anyhow::anyhow!("{}，已自动回滚到部署前的证书", e)),
+                Err(rollback_error) => Err(anyhow::anyhow!("{}，自动回滚也失败了: {}", e, rollback_error)),
+            };
+        }
+        tracing::info!("证书部署完成，DeployRecordId: {}", deploy_record_id);
+    } else {
+        tracing::info!("证书部署成功，DeployRecordId: {}", deploy_record_id);
+    }
+    Ok(Some(deploy_record_id))
+}
 
+// 忽略证书到期通知，证书由本程序自动续期，不需要腾讯云的到期提醒
+async fn ignore_expiring_notification(credentials: &CredentialProvider, certificate_id: &str, host: &str) -> anyhow::Result<()> {
     // 3. 忽略证书到期通知
-    println!("忽略证书到期通知 {}...", certificate_id);
+    tracing::info!("忽略证书到期通知 {}...", certificate_id);
     let deploy_param = modify_certificate_notification(
-        secret_id.to_string(),
-        secret_key.to_string(),
+        &credentials.get().await?,
         certificate_id.to_string(),
-        host,
+        host.to_string(),
     ).await?;
     let modify_notification_response = serde_json::from_str::<TencentCloudResponse<ModifyCertificateNotificationData>>(&deploy_param)?;
-    if modify_notification_response.response.error.is_some() {
-        println!("忽略证书到期通知失败");
-        return Err(anyhow::anyhow!(modify_notification_response.response.error.unwrap().to_string()));
+    if let Some(error) = modify_notification_response.response.error {
+        tracing::error!("忽略证书到期通知失败");
+        return Err(error.with_request_id(&modify_notification_response.response.request_id).into());
     }
     let certificate_ids = modify_notification_response.response.data.unwrap().certificate_ids;
-    println!("忽略证书到期通知成功，CertificateIds: {:?}", certificate_ids);
+    tracing::info!("忽略证书到期通知成功，CertificateIds: {:?}", certificate_ids);
+    Ok(())
+}
+
+// 查询已上传证书的 SAN，多个证书时取并集
+async fn certificate_sans(credentials: &CredentialProvider, certificate_ids: &[String], host: &str) -> anyhow::Result<Vec<String>> {
+    let mut sans = vec![];
+    for certificate_id in certificate_ids {
+        let public_key = certificate_public_key(credentials, certificate_id, host).await?;
+        sans.extend(service::cert::dns_names(&service::cert::leaf(&public_key)?));
+    }
+    Ok(sans)
+}
+
+// 查询已上传证书的内容
+async fn certificate_public_key(credentials: &CredentialProvider, certificate_id: &str, host: &str) -> anyhow::Result<String> {
+    let detail_param = describe_certificate_detail(
+        &credentials.get().await?,
+        certificate_id.to_string(),
+        host.to_string(),
+    ).await?;
+    let detail_response = serde_json::from_str::<TencentCloudResponse<DescribeCertificateDetailData>>(&detail_param)?;
+    if let Some(error) = detail_response.response.error {
+        return Err(error.with_request_id(&detail_response.response.request_id).into());
+    }
+    detail_response.response.data
+        .and_then(|data| data.certificate_public_key)
+        .ok_or(anyhow::anyhow!("无法获取证书 {} 的内容", certificate_id))
+}
+
+// 一键更新，找到每个新证书要替换的旧证书，把绑定旧证书的云资源都更换为新证书并等待完成，返回第一个更新记录的 DeployRecordId
+// 旧证书优先使用状态文件中记录的上次部署的证书，否则按域名查询
+async fn replace(
+    job: &config::file_conf::Job,
+    credentials: &CredentialProvider,
+    certificate_ids: &[String],
+    state_file: &str,
+    host: &str,
+) -> anyhow::Result<Option<i64>> {
+    let args = &job.args;
+    let recorded = service::state::load(state_file)?.jobs.get(&job.name).cloned();
+    let recorded_ids = recorded.map(|job_state| vec![Some(job_state.certificate_id), job_state.ecc_certificate_id]).unwrap_or_default();
+    let resource_types = args.replace_resource_types();
+    let resource_types_regions: Vec<(String, String)> = match &args.region {
+        Some(region) => resource_types
+            .iter()
+            .filter(|resource_type| resource_type.parse::<service::resource::ResourceType>().is_ok_and(|resource_type| resource_type.requires_region()))
+            .map(|resource_type| (resource_type.clone(), region.clone()))
+            .collect(),
+        None => vec![],
+    };
+    let timeout = std::time::Duration::from_secs(args.deploy_wait_timeout.unwrap_or(600));
+
+    let mut first_deploy_record_id = None;
+    for (index, certificate_id) in certificate_ids.iter().enumerate() {
+        let recorded_id = recorded_ids.get(index).cloned().flatten().filter(|id| !id.is_empty());
+        if recorded_id.as_ref() == Some(certificate_id) {
+            tracing::info!("证书 {} 已经是上次部署的证书，跳过一键更新", certificate_id);
+            continue;
+        }
+        let old_certificate_id = match recorded_id {
+            Some(id) => id,
+            None => {
+                let public_key = certificate_public_key(credentials, certificate_id, host).await?;
+                service::replace::find_old_certificate(credentials, &public_key, certificate_id, host).await?
+                    .ok_or(anyhow::anyhow!("没有找到证书 {} 要替换的旧证书", certificate_id))?
+            },
+        };
+        tracing::info!("正在把绑定旧证书 {} 的 {:?} 资源更新为证书 {}...", old_certificate_id, resource_types, certificate_id);
+        let deploy_record_id = service::replace::update(
+            credentials,
+            certificate_id,
+            &old_certificate_id,
+            &resource_types,
+            &resource_types_regions,
+            host,
+        ).await?;
+        first_deploy_record_id.get_or_insert(deploy_record_id);
+        tracing::info!("一键更新任务已创建，DeployRecordId: {}，等待更新完成...", deploy_record_id);
+        service::replace::wait(credentials, deploy_record_id, timeout, host).await?;
+        tracing::info!("一键更新完成，DeployRecordId: {}", deploy_record_id);
+    }
+    Ok(first_deploy_record_id)
+}
+
+// 开启自动发现时，把 EO 中被证书 SAN 覆盖的加速域名合并到任务的域名列表
+async fn discover_instances(job: &config::file_conf::Job, credentials: &CredentialProvider, sans: &[String]) -> anyhow::Result<config::file_conf::Job> {
+    let mut job = job.clone();
+    if !job.args.auto_discover.unwrap_or(false) {
+        return Ok(job);
+    }
+    let mut instance_id_list = job.args.instance_id_list.clone().unwrap_or_default();
+    for domain in service::discover::discover(credentials, sans, &job.args).await? {
+        if !instance_id_list.contains(&domain) {
+            instance_id_list.push(domain);
+        }
+    }
+    if instance_id_list.is_empty() {
+        return Err(anyhow::anyhow!("没有找到被证书覆盖的加速域名"));
+    }
+    job.args.instance_id_list = Some(instance_id_list);
+    Ok(job)
+}
+
+async fn deploy(job: &config::file_conf::Job, state_file: &str) -> anyhow::Result<DeployResult> {
+    let credentials = CredentialProvider::new(&job.args);
+    let host = ssl_host(job.args.intl.unwrap_or(false));
+
+    let sans = service::cert::dns_names(&service::cert::leaf(&std::fs::read_to_string(job.args.public_key_file_path.as_ref().unwrap())?)?);
+    let job = discover_instances(job, &credentials, &sans).await?;
+    let (fingerprint, certificate_ids) = upload(&job.args, &credentials, &host).await?;
+    let deploy_record_id = if job.args.replace.unwrap_or(false) {
+        replace(&job, &credentials, &certificate_ids, state_file, &host).await?
+    } else {
+        deploy_to_instances(&job, &credentials, &certificate_ids, &host, state_file).await?
+    };
+    for certificate_id in &certificate_ids {
+        ignore_expiring_notification(&credentials, certificate_id, &host).await?;
+    }
+    if job.args.cleanup.unwrap_or(false) {
+        if is_verified(&job.args) {
+            if let Err(e) = cleanup(&job, &credentials, &certificate_ids, state_file, false, &host).await {
+                tracing::warn!("清理旧证书失败: {}", e);
+            }
+        } else {
+            tracing::warn!("没有等待部署完成，无法确认部署成功，跳过清理旧证书");
+        }
+    }
+
+    anyhow::Ok(DeployResult {
+        fingerprint,
+        certificate_ids,
+        deploy_record_id,
+        instance_id_list: job.args.instance_id_list.unwrap_or_default(),
+    })
+}
+
+// 部署结果是否已经确认，teo 部署方式和一键更新是同步确认的，ssl 部署方式需要等待部署完成
+fn is_verified(args: &config::args_conf::Args) -> bool {
+    args.replace.unwrap_or(false) || args.backend() == "teo" || args.deploy_wait.unwrap_or(false)
+}
+
+// 清理被当前证书取代的旧证书，certificate_ids 与任务的 RSA、ECC 证书文件一一对应，
+// 状态文件中记录的部署前的证书是回滚的目标，不清理
+async fn cleanup(
+    job: &config::file_conf::Job,
+    credentials: &CredentialProvider,
+    certificate_ids: &[String],
+    state_file: &str,
+    dry_run: bool,
+    host: &str,
+) -> anyhow::Result<Vec<String>> {
+    let args = &job.args;
+    let state = service::state::load(state_file)?;
+    let rollback_certificate_ids: Vec<String> = state
+        .jobs
+        .get(&job.name)
+        .map(|job_state| job_state.previous_certificate_ids.values().flatten().cloned().collect())
+        .unwrap_or_default();
+    let public_key_file_paths = [&args.public_key_file_path, &args.ecc_public_key_file_path].into_iter().flatten();
+    let mut removed = vec![];
+    for (public_key_file_path, certificate_id) in public_key_file_paths.zip(certificate_ids) {
+        let certificate_public_key = std::fs::read_to_string(public_key_file_path)?;
+        removed.extend(service::cleanup::cleanup(
+            credentials,
+            &certificate_public_key,
+            certificate_id,
+            &rollback_certificate_ids,
+            args.cleanup_keep.unwrap_or(0),
+            dry_run,
+            host,
+        ).await?);
+    }
+    Ok(removed)
+}
+
+// 通知事件的公共部分
+fn notify_event(
+    job: &config::file_conf::Job,
+    action: &str,
+    domains: &[String],
+    certificate_ids: &[String],
+    started: std::time::Instant,
+) -> Event {
+    Event {
+        action: action.to_string(),
+        job: job.name.clone(),
+        domains: domains.to_vec(),
+        certificate_ids: certificate_ids.to_vec(),
+        duration: Some(started.elapsed().as_secs()),
+        ..Default::default()
+    }
+}
+
+// 执行一个任务的上传部署并发送通知，并把部署结果记录到状态文件
+async fn run_job(job: &config::file_conf::Job, state_file: &str) -> anyhow::Result<DeployResult> {
+    tracing::info!("开始执行任务 {}", job.name);
+    let args = &job.args;
+    let started = std::time::Instant::now();
+    match deploy(job, state_file).await {
+        Ok(result) => {
+            let mut event = notify_event(job, "deploy", &result.instance_id_list, &result.certificate_ids, started);
+            event.fingerprint = Some(result.fingerprint.clone());
+            event.deploy_record_id = result.deploy_record_id;
+            if let Some(cert) = local_certificate(args) {
+                event = event.certificate(&cert);
+            }
+            service::notify::send(args, &event.success()).await;
+            if let Err(e) = record_state(state_file, &job.name, &result) {
+                tracing::warn!("记录部署状态失败: {}", e);
+            }
+            Ok(result)
+        },
+        Err(e) => {
+            tracing::error!("部署失败: {}", e);
+            let mut event = notify_event(job, "deploy", args.instance_id_list.as_deref().unwrap_or_default(), &[], started);
+            event.fingerprint = local_fingerprint(args).ok();
+            if let Some(cert) = local_certificate(args) {
+                event = event.certificate(&cert);
+            }
+            service::notify::send(args, &event.failure(&e)).await;
+            if let Err(e) = record_failure(state_file, &job.name, &e) {
+                tracing::warn!("记录部署状态失败: {}", e);
+            }
+            Err(e)
+        },
+    }
+}
+
+fn record_state(state_file: &str, name: &str, result: &DeployResult) -> anyhow::Result<()> {
+    let mut state = service::state::load(state_file)?;
+    let job_state = state.jobs.entry(name.to_string()).or_default();
+    job_state.fingerprint = result.fingerprint.clone();
+    job_state.certificate_id = result.certificate_ids[0].clone();
+    job_state.ecc_certificate_id = result.certificate_ids.get(1).cloned();
+    job_state.deploy_record_id = result.deploy_record_id;
+    job_state.deployed_at = chrono::Local::now().to_rfc3339();
+    job_state.push_history(service::state::History {
+        status: "success".to_string(),
+        certificate_ids: result.certificate_ids.clone(),
+        deploy_record_id: result.deploy_record_id,
+        error: None,
+        deployed_at: job_state.deployed_at.clone(),
+    });
+    service::state::save(state_file, &state)
+}
+
+fn record_failure(state_file: &str, name: &str, error: &anyhow::Error) -> anyhow::Result<()> {
+    let mut state = service::state::load(state_file)?;
+    state.jobs.entry(name.to_string()).or_default().push_history(service::state::History {
+        status: "failure".to_string(),
+        error: Some(error.to_string()),
+        deployed_at: chrono::Local::now().to_rfc3339(),
+        ..Default::default()
+    });
+    service::state::save(state_file, &state)
+}
+
+fn record_previous(state_file: &str, name: &str, previous: &HashMap<String, Vec<String>>, certificate_ids: &[String]) -> anyhow::Result<()> {
+    if previous.is_empty() {
+        return Ok(());
+    }
+    let mut state = service::state::load(state_file)?;
+    state.jobs.entry(name.to_string()).or_default().record_previous(previous, certificate_ids);
+    service::state::save(state_file, &state)
+}
+
+// 按状态文件中记录的部署前证书回滚一个任务，并发送通知
+async fn rollback_job(job: &config::file_conf::Job, state_file: &str) -> anyhow::Result<()> {
+    tracing::info!("开始回滚任务 {}", job.name);
+    let args = &job.args;
+    let state = service::state::load(state_file)?;
+    let previous = state.jobs.get(&job.name).map(|job_state| job_state.previous_certificate_ids.clone()).unwrap_or_default();
+    let credentials = CredentialProvider::new(args);
+    let started = std::time::Instant::now();
+    let result = rollback(args, &credentials, &previous, &ssl_host(args.intl.unwrap_or(false))).await;
+    match &result {
+        Ok(_) => {
+            let mut domains: Vec<String> = previous.keys().cloned().collect();
+            domains.sort();
+            let mut certificate_ids: Vec<String> = previous.values().flatten().cloned().collect();
+            certificate_ids.sort();
+            certificate_ids.dedup();
+            let event = notify_event(job, "rollback", &domains, &certificate_ids, started);
+            service::notify::send(args, &event.success()).await
+        },
+        Err(e) => {
+            tracing::error!("回滚失败: {}", e);
+            let event = notify_event(job, "rollback", args.instance_id_list.as_deref().unwrap_or_default(), &[], started);
+            service::notify::send(args, &event.failure(e)).await
+        },
+    }
+    result
+}
+
+// 输出每个任务的执行结果，有任务失败时返回错误
+fn summarize(results: Vec<(String, Option<anyhow::Error>)>) -> anyhow::Result<()> {
+    tracing::info!("执行结果汇总:");
+    for (name, error) in &results {
+        match error {
+            None => tracing::info!("  ✅ {}", name),
+            Some(e) => tracing::info!("  ❌ {}: {}", name, e),
+        }
+    }
+    let failed = results.iter().filter(|(_, error)| error.is_some()).count();
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} 个任务中有 {} 个失败", results.len(), failed));
+    }
+    Ok(())
+}
+
+async fn run(jobs: &[config::file_conf::Job], state_file: &str) -> anyhow::Result<()> {
+    let mut results = vec![];
+    for job in jobs {
+        results.push((job.name.clone(), run_job(job, state_file).await.err()));
+    }
+    summarize(results)
+}
+
+// 常驻模式定时执行，证书指纹与上次成功部署的相同时跳过
+async fn run_changed(jobs: &[config::file_conf::Job], state_file: &str) {
+    let state = match service::state::load(state_file) {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            service::state::State::default()
+        },
+    };
+    for job in jobs {
+        let fingerprint = local_fingerprint(&job.args).ok();
+        let deployed = state.jobs.get(&job.name).map(|job_state| &job_state.fingerprint);
+        if fingerprint.is_some() && fingerprint.as_ref() == deployed {
+            tracing::info!("任务 {} 的证书没有变化，跳过", job.name);
+            continue;
+        }
+        let _ = run_job(job, state_file).await;
+    }
+}
+
+// 处理常驻模式下收到的 TG 机器人命令，返回回复的文本
+async fn bot_command(jobs: &[config::file_conf::Job], state_file: &str, command: &service::tg_bot::BotCommand) -> String {
+    let state = match service::state::load(state_file) {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            service::state::State::default()
+        },
+    };
+    match command {
+        service::tg_bot::BotCommand::Status => jobs
+            .iter()
+            .map(|job| {
+                let not_after = local_certificate(&job.args).and_then(|cert| service::cert::not_after(&cert).ok());
+                service::tg_bot::status_text(&job.name, not_after, state.jobs.get(&job.name))
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+        service::tg_bot::BotCommand::History => jobs
+            .iter()
+            .map(|job| service::tg_bot::history_text(&job.name, state.jobs.get(&job.name)))
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+        service::tg_bot::BotCommand::Deploy(name) => {
+            let job = match (name.as_str(), jobs) {
+                ("", [job]) => Some(job),
+                _ => jobs.iter().find(|job| &job.name == name),
+            };
+            let Some(job) = job else {
+                let names: Vec<&str> = jobs.iter().map(|job| job.name.as_str()).collect();
+                return format!("没有找到任务 {}，可选任务: {}", name, names.join(", "));
+            };
+            match run_job(job, state_file).await {
+                Ok(result) => format!(
+                    "任务 {} 部署成功，证书ID {}，部署记录ID {}",
+                    job.name,
+                    result.certificate_ids.join(", "),
+                    result.deploy_record_id.map(|id| id.to_string()).unwrap_or("-".to_string()),
+                ),
+                Err(e) => format!("任务 {} 部署失败: {}", job.name, e),
+            }
+        },
+        service::tg_bot::BotCommand::Help => service::tg_bot::HELP.to_string(),
+    }
+}
+
+// upload 子命令，只上传证书并输出 CertificateId
+async fn upload_job(job: &config::file_conf::Job) -> anyhow::Result<()> {
+    tracing::info!("开始上传任务 {} 的证书", job.name);
+    let credentials = CredentialProvider::new(&job.args);
+    let (_, certificate_ids) = upload(&job.args, &credentials, &ssl_host(job.args.intl.unwrap_or(false))).await?;
+    println!("{}\t{}", job.name, certificate_ids.join("\t"));
+    Ok(())
+}
+
+// deploy 子命令，把已上传的证书部署到任务的域名并发送通知
+async fn deploy_job(job: &config::file_conf::Job, certificate_ids: &[String], state_file: &str) -> anyhow::Result<()> {
+    tracing::info!("开始部署任务 {}", job.name);
+    let args = &job.args;
+    let credentials = CredentialProvider::new(args);
+    let host = ssl_host(args.intl.unwrap_or(false));
+    let started = std::time::Instant::now();
+    let deployed = async {
+        let job = if args.auto_discover.unwrap_or(false) {
+            let sans = certificate_sans(&credentials, certificate_ids, &host).await?;
+            discover_instances(job, &credentials, &sans).await?
+        } else {
+            job.clone()
+        };
+        let deploy_record_id = if args.replace.unwrap_or(false) {
+            replace(&job, &credentials, certificate_ids, state_file, &host).await?
+        } else {
+            deploy_to_instances(&job, &credentials, certificate_ids, &host, state_file).await?
+        };
+        anyhow::Ok((deploy_record_id, job.args.instance_id_list.unwrap_or_default()))
+    };
+    match deployed.await {
+        Ok((deploy_record_id, instance_id_list)) => {
+            println!("{}\t{}", job.name, deploy_record_id.map(|id| id.to_string()).unwrap_or("-".to_string()));
+            let mut event = notify_event(job, "deploy", &instance_id_list, certificate_ids, started);
+            event.deploy_record_id = deploy_record_id;
+            service::notify::send(args, &event.success()).await;
+            Ok(())
+        },
+        Err(e) => {
+            tracing::error!("部署失败: {}", e);
+            let event = notify_event(job, "deploy", args.instance_id_list.as_deref().unwrap_or_default(), certificate_ids, started);
+            service::notify::send(args, &event.failure(&e)).await;
+            Err(e)
+        },
+    }
+}
 
-    anyhow::Ok(())
+// cleanup 子命令，按本地证书找到已上传的当前证书，清理被它取代的旧证书
+async fn cleanup_job(job: &config::file_conf::Job, state_file: &str, dry_run: bool) -> anyhow::Result<()> {
+    tracing::info!("开始清理任务 {} 的旧证书", job.name);
+    let args = &job.args;
+    let credentials = CredentialProvider::new(args);
+    let host = ssl_host(args.intl.unwrap_or(false));
+    let mut certificate_ids = vec![];
+    for public_key_file_path in [&args.public_key_file_path, &args.ecc_public_key_file_path].into_iter().flatten() {
+        let certificate_public_key = std::fs::read_to_string(public_key_file_path)?;
+        let fingerprint = service::cert::fingerprint(&service::cert::leaf(&certificate_public_key)?)?;
+        let certificate_id = find_uploaded_certificate(&credentials, &certificate_public_key, &fingerprint, &host).await?
+            .ok_or(anyhow::anyhow!("证书 {} 还没有上传", public_key_file_path))?;
+        certificate_ids.push(certificate_id);
+    }
+    for certificate_id in cleanup(job, &credentials, &certificate_ids, state_file, dry_run, &host).await? {
+        println!("{}\t{}", job.name, certificate_id);
+    }
+    Ok(())
+}
+
+// 读取任务的本地证书，同时部署 RSA 和 ECC 证书时 RSA 证书在前
+fn local_certificates(args: &Args) -> anyhow::Result<Vec<service::check::CheckedCertificate>> {
+    let mut local = vec![];
+    for public_key_file_path in [&args.public_key_file_path, &args.ecc_public_key_file_path].into_iter().flatten() {
+        let pem = std::fs::read_to_string(public_key_file_path)
+            .map_err(|e| anyhow::anyhow!("读取证书文件 {} 失败: {}", public_key_file_path, e))?;
+        local.push(service::check::CheckedCertificate::from_pem(public_key_file_path, None, &pem)?);
+    }
+    Ok(local)
+}
+
+// 查询任务的域名在 EO 中当前绑定的证书，开启自动发现时包括被 sans 覆盖的加速域名，
+// 返回 (域名, 证书配置模式, 绑定的证书)，不是 EO 加速域名时模式为空，只有 SSL 托管证书才查询证书内容
+async fn bound_certificates(
+    args: &Args,
+    credentials: &CredentialProvider,
+    sans: &[String],
+) -> anyhow::Result<Vec<(String, Option<String>, Vec<service::check::CheckedCertificate>)>> {
+    let host = ssl_host(args.intl.unwrap_or(false));
+    let acceleration_domains: HashMap<String, service::teo_api::AccelerationDomain> =
+        service::discover::acceleration_domains(credentials, &service::teo_api::teo_host(args.intl.unwrap_or(false))).await?
+            .into_iter()
+            .map(|domain| (domain.domain_name.to_lowercase(), domain))
+            .collect();
+    let mut domains = args.instance_id_list.clone().unwrap_or_default();
+    if args.auto_discover.unwrap_or(false) {
+        let names: Vec<String> = acceleration_domains.values().map(|domain| domain.domain_name.clone()).collect();
+        for domain in service::discover::select(
+            &names,
+            sans,
+            args.include_domains.as_deref().unwrap_or_default(),
+            args.exclude_domains.as_deref().unwrap_or_default(),
+        ) {
+            if !domains.contains(&domain) {
+                domains.push(domain);
+            }
+        }
+    }
+
+    // 多个域名绑定同一个证书时只查询一次
+    let mut certificates: HashMap<String, service::check::CheckedCertificate> = HashMap::new();
+    let mut bound_list = vec![];
+    for domain in domains {
+        let Some(acceleration_domain) = acceleration_domains.get(&domain.to_lowercase()) else {
+            bound_list.push((domain, None, vec![]));
+            continue;
+        };
+        let certificate = acceleration_domain.certificate.as_ref();
+        let mode = certificate.and_then(|certificate| certificate.mode.clone()).unwrap_or("disable".to_string());
+        let mut bound = vec![];
+        if mode == "sslcert" {
+            for certificate_id in certificate.and_then(|certificate| certificate.list.as_ref()).into_iter().flatten().map(|info| &info.cert_id) {
+                if !certificates.contains_key(certificate_id) {
+                    let public_key = certificate_public_key(credentials, certificate_id, &host).await?;
+                    certificates.insert(
+                        certificate_id.clone(),
+                        service::check::CheckedCertificate::from_pem(&domain, Some(certificate_id), &public_key)?,
+                    );
+                }
+                bound.push(service::check::CheckedCertificate { name: domain.clone(), ..certificates[certificate_id].clone() });
+            }
+        }
+        bound_list.push((domain, Some(mode), bound));
+    }
+    Ok(bound_list)
+}
+
+// 检查一个任务的本地证书和 EO 域名绑定的证书，返回检查的证书、域名和发现的问题
+async fn check_certificates(
+    job: &config::file_conf::Job,
+    warning_days: i64,
+) -> anyhow::Result<(Vec<service::check::CheckedCertificate>, Vec<String>, Vec<String>)> {
+    let args = &job.args;
+    let now = chrono::Utc::now();
+    let local = local_certificates(args)?;
+    let mut problems: Vec<String> = local.iter().filter_map(|certificate| certificate.expiry_problem(warning_days, now)).collect();
+    let mut checked = local.clone();
+    if args.resource() != service::resource::ResourceType::Teo {
+        tracing::warn!("任务 {} 的资源类型不是 teo，只检查本地证书", job.name);
+        return Ok((checked, vec![], problems));
+    }
+
+    let credentials = CredentialProvider::new(args);
+    let sans: Vec<String> = local.iter().flat_map(|certificate| certificate.sans.clone()).collect();
+    let mut domains = vec![];
+    let mut expiry_checked = std::collections::HashSet::new();
+    for (domain, mode, bound) in bound_certificates(args, &credentials, &sans).await? {
+        match mode.as_deref() {
+            None => problems.push(format!("域名 {} 不是 EO 的加速域名", domain)),
+            Some("sslcert") => problems.extend(service::check::mismatch_problem(&domain, &local, &bound)),
+            Some(mode) => problems.push(format!("域名 {} 没有使用 SSL 托管证书，当前为 {}", domain, mode)),
+        }
+        for certificate in &bound {
+            if expiry_checked.insert(certificate.certificate_id.clone()) {
+                problems.extend(certificate.expiry_problem(warning_days, now));
+            }
+        }
+        checked.extend(bound);
+        domains.push(domain);
+    }
+    Ok((checked, domains, problems))
+}
+
+// plan 子命令，对比本地证书和 EO 域名当前绑定的证书，生成部署计划，只调用查询接口
+async fn plan_job(job: &config::file_conf::Job) -> anyhow::Result<service::plan::JobPlan> {
+    tracing::info!("开始生成任务 {} 的部署计划", job.name);
+    let args = &job.args;
+    let credentials = CredentialProvider::new(args);
+    let host = ssl_host(args.intl.unwrap_or(false));
+    let mut local = local_certificates(args)?;
+    for certificate in &mut local {
+        let certificate_public_key = std::fs::read_to_string(&certificate.name)?;
+        certificate.certificate_id = find_uploaded_certificate(&credentials, &certificate_public_key, &certificate.fingerprint, &host).await?;
+    }
+    if args.resource() != service::resource::ResourceType::Teo {
+        tracing::warn!("任务 {} 的资源类型不是 teo，只对比本地证书", job.name);
+        return Ok(service::plan::JobPlan::new(&job.name, local, vec![]));
+    }
+    let sans: Vec<String> = local.iter().flat_map(|certificate| certificate.sans.clone()).collect();
+    let domains = bound_certificates(args, &credentials, &sans).await?
+        .into_iter()
+        .map(|(domain, mode, current)| service::plan::DomainPlan::new(&domain, mode.as_deref(), current, &local))
+        .collect();
+    Ok(service::plan::JobPlan::new(&job.name, local, domains))
+}
+
+// check 子命令，输出检查的证书，发现问题或检查失败时发送通知
+async fn check_job(job: &config::file_conf::Job) -> anyhow::Result<()> {
+    tracing::info!("开始检查任务 {} 的证书", job.name);
+    let args = &job.args;
+    let warning_days = args.expiry_warning_days.unwrap_or(14);
+    let started = std::time::Instant::now();
+    let mut event = notify_event(job, "check", args.instance_id_list.as_deref().unwrap_or_default(), &[], started);
+    event.fingerprint = local_fingerprint(args).ok();
+    if let Some(cert) = local_certificate(args) {
+        event = event.certificate(&cert);
+    }
+    match check_certificates(job, warning_days).await {
+        Ok((checked, domains, problems)) => {
+            let now = chrono::Utc::now();
+            for certificate in &checked {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    job.name,
+                    certificate.name,
+                    certificate.certificate_id.as_deref().unwrap_or("-"),
+                    certificate.not_after.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                    certificate.days_remaining(now),
+                );
+            }
+            if problems.is_empty() {
+                tracing::info!("任务 {} 的证书检查通过", job.name);
+                return Ok(());
+            }
+            for problem in &problems {
+                tracing::warn!("{}", problem);
+            }
+            let count = problems.len();
+            event.domains = domains;
+            event.duration = Some(started.elapsed().as_secs());
+            service::notify::send(args, &event.problems(problems)).await;
+            Err(anyhow::anyhow!("发现 {} 个问题", count))
+        },
+        Err(e) => {
+            tracing::error!("检查失败: {}", e);
+            event.duration = Some(started.elapsed().as_secs());
+            service::notify::send(args, &event.failure(&e)).await;
+            Err(e)
+        },
+    }
+}
+
+// list 子命令，查询证书列表
+async fn list(args: &config::args_conf::Args, filter: &CertificateFilter) -> anyhow::Result<()> {
+    let credentials = CredentialProvider::new(args);
+    let describe_param = describe_certificates(
+        &credentials.get().await?,
+        filter,
+        ssl_host(args.intl.unwrap_or(false)),
+    ).await?;
+    let describe_response = serde_json::from_str::<TencentCloudResponse<DescribeCertificatesData>>(&describe_param)?;
+    if let Some(error) = describe_response.response.error {
+        return Err(error.with_request_id(&describe_response.response.request_id).into());
+    }
+    let data = describe_response.response.data.ok_or(anyhow::anyhow!("响应中没有证书列表"))?;
+    println!("CertificateId\t状态\t到期时间\t域名\tSAN");
+    for certificate in &data.certificates {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            certificate.certificate_id,
+            certificate.status_name.as_deref().unwrap_or("-"),
+            certificate.cert_end_time.as_deref().unwrap_or("-"),
+            certificate.domain.as_deref().unwrap_or("-"),
+            certificate.subject_alt_name.as_ref().map(|names| names.join(",")).unwrap_or("-".to_string()),
+        );
+    }
+    println!("共 {} 个证书", data.total_count);
+    Ok(())
+}
+
+// status 子命令，查询部署记录详情
+async fn status(args: &config::args_conf::Args, deploy_record_id: i64) -> anyhow::Result<()> {
+    let credentials = CredentialProvider::new(args);
+    let details = deploy_record_details(&credentials, deploy_record_id, &ssl_host(args.intl.unwrap_or(false))).await?;
+    println!("域名\t状态\t证书\t原证书\t错误信息");
+    for detail in &details {
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            detail.names().join(","),
+            detail.status_text(),
+            detail.cert_id.as_deref().unwrap_or("-"),
+            detail.old_cert_id.as_deref().unwrap_or("-"),
+            detail.error_msg.as_deref().unwrap_or(""),
+        );
+    }
+    Ok(())
+}
+
+// delete 子命令，删除证书
+async fn delete(args: &config::args_conf::Args, certificate_id: &str) -> anyhow::Result<()> {
+    let credentials = CredentialProvider::new(args);
+    let delete_param = delete_certificate(
+        &credentials.get().await?,
+        certificate_id.to_string(),
+        ssl_host(args.intl.unwrap_or(false)),
+    ).await?;
+    let delete_response = serde_json::from_str::<TencentCloudResponse<DeleteCertificateData>>(&delete_param)?;
+    if let Some(error) = delete_response.response.error {
+        return Err(error.with_request_id(&delete_response.response.request_id).into());
+    }
+    if !delete_response.response.data.map(|data| data.delete_result).unwrap_or(false) {
+        return Err(anyhow::anyhow!("证书 {} 删除失败", certificate_id));
+    }
+    println!("证书 {} 已删除", certificate_id);
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let args = match config::args_conf::parse() {
+    let args = config::args_conf::parse();
+    config::log_conf::init(args.as_ref().ok().and_then(|args| args.log_level.as_deref()));
+    let args = match args {
         Ok(args) => args,
         Err(e) => {
-            println!("参数解析失败: {}", e);
+            tracing::error!("参数解析失败: {}", e);
             return Err(e);
         }
     };
-    match deploy(&args).await {
-        Ok(_) => {
-            service::tg_notify::send_msg(&args, format!("✅证书部署到 `{:?}` 成功", args.instance_id_list)).await;
-            Ok(())
-        },
+    let command = args.command.clone();
+    let debounce = std::time::Duration::from_secs(args.watch_debounce.unwrap_or(10));
+    let cron = args.cron.clone().unwrap_or("0 3 * * *".to_string());
+    let jitter = std::time::Duration::from_secs(args.cron_jitter.unwrap_or(300));
+    // 所有子命令使用同一个默认状态文件，rollback 才能读到部署时记录的证书
+    let state_file = args.state_file.clone().unwrap_or(DEFAULT_STATE_FILE.to_string());
+    let defaults = match config::file_conf::defaults(args.clone()) {
+        Ok(defaults) => defaults,
         Err(e) => {
-            println!("部署失败: {}", e);
-            service::tg_notify::send_msg(&args, format!("❌证书部署到 `{:?}` 失败，错误信息: {}", args.instance_id_list, e)).await;
-            Err(e)
+            tracing::error!("参数解析失败: {}", e);
+            return Err(e);
+        }
+    };
+    let jobs = match config::file_conf::jobs(args) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            tracing::error!("参数解析失败: {}", e);
+            return Err(e);
+        }
+    };
+    let check = match &command {
+        None | Some(Command::Run) | Some(Command::Watch) | Some(Command::Daemon) => config::file_conf::check(&jobs, Args::check),
+        Some(Command::Upload) | Some(Command::Cleanup { .. }) | Some(Command::Check) | Some(Command::Plan { .. }) => config::file_conf::check(&jobs, Args::check_certificate_files),
+        Some(Command::Deploy { .. }) => config::file_conf::check(&jobs, Args::check_deploy),
+        _ => Ok(()),
+    };
+    if let Err(e) = check {
+        tracing::error!("参数解析失败: {}", e);
+        return Err(e);
+    }
+    match command {
+        None | Some(Command::Run) => run(&jobs, &state_file).await,
+        Some(Command::Upload) => {
+            let mut results = vec![];
+            for job in &jobs {
+                results.push((job.name.clone(), upload_job(job).await.err()));
+            }
+            summarize(results)
+        },
+        Some(Command::Deploy { certificate_ids }) => {
+            let mut results = vec![];
+            for job in &jobs {
+                results.push((job.name.clone(), deploy_job(job, &certificate_ids, &state_file).await.err()));
+            }
+            summarize(results)
+        },
+        Some(Command::List { search_key, status, upload_only, expiring, offset, limit }) => {
+            let filter = CertificateFilter {
+                search_key,
+                certificate_status: status,
+                upload_only,
+                expiring,
+                offset,
+                limit,
+            };
+            list(&defaults, &filter).await
+        },
+        Some(Command::Status { deploy_record_id }) => status(&defaults, deploy_record_id).await,
+        Some(Command::Delete { certificate_id }) => delete(&defaults, &certificate_id).await,
+        Some(Command::Cleanup { dry_run }) => {
+            let mut results = vec![];
+            for job in &jobs {
+                results.push((job.name.clone(), cleanup_job(job, &state_file, dry_run).await.err()));
+            }
+            summarize(results)
+        },
+        Some(Command::Watch) => {
+            service::watch::watch(&jobs, debounce, async |job| {
+                let _ = run_job(job, &state_file).await;
+            }).await
+        },
+        Some(Command::Daemon) => {
+            let schedule = service::daemon::parse_schedule(&cron)?;
+            tracing::info!("常驻模式启动，cron: {}，状态文件: {}", cron, state_file);
+            // 定时部署和机器人命令触发的部署不同时执行
+            let lock = tokio::sync::Mutex::new(());
+            let shutdown = tokio::sync::Notify::new();
+            let scheduled = async {
+                let result = service::daemon::run(&schedule, jitter, async || {
+                    let _guard = lock.lock().await;
+                    run_changed(&jobs, &state_file).await;
+                }).await;
+                shutdown.notify_one();
+                result
+            };
+            let commands = async {
+                let Some(mut bot) = service::tg_bot::Bot::new(&defaults) else {
+                    return Ok(());
+                };
+                bot.run(&shutdown, async |command| {
+                    let _guard = lock.lock().await;
+                    bot_command(&jobs, &state_file, command).await
+                }).await
+            };
+            let (result, commands) = tokio::join!(scheduled, commands);
+            result.and(commands)
+        },
+        Some(Command::Check) => {
+            println!("任务\t证书\tCertificateId\t到期时间\t剩余天数");
+            let mut results = vec![];
+            for job in &jobs {
+                results.push((job.name.clone(), check_job(job).await.err()));
+            }
+            summarize(results)
+        },
+        Some(Command::Plan { json }) => {
+            let mut plans = vec![];
+            let mut results = vec![];
+            for job in &jobs {
+                match plan_job(job).await {
+                    Ok(plan) => {
+                        plans.push(plan);
+                        results.push((job.name.clone(), None));
+                    },
+                    Err(e) => results.push((job.name.clone(), Some(e))),
+                }
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plans)?);
+            } else {
+                println!("{}", plans.iter().map(|plan| plan.table()).collect::<Vec<String>>().join("\n\n"));
+            }
+            summarize(results)
+        },
+        Some(Command::Rollback) => {
+            let mut results = vec![];
+            for job in &jobs {
+                results.push((job.name.clone(), rollback_job(job, &state_file).await.err()));
+            }
+            summarize(results)
         },
     }
 }
@@ -202,16 +1275,18 @@ async fn main() -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::service::credential::Credential;
 
     #[tokio::test]
     async fn test_upload_certificate() {
         let secret_id = std::env::var("TENCENTCLOUD_SECRET_ID").unwrap();
         let secret_key = std::env::var("TENCENTCLOUD_SECRET_KEY").unwrap();
+        let credential = Credential::new(secret_id, secret_key);
         let public_key = "test_public_key";
         let private_key = "test_private_key";
         let host = "ssl.intl.tencentcloudapi.com";
 
-        let result = upload_certificate(secret_id, secret_key, public_key.to_string(), private_key.to_string(), host.to_string()).await;
+        let result = upload_certificate(&credential, public_key.to_string(), private_key, Some("teo"), host.to_string()).await;
         println!("{:?}", result);
         assert!(result.is_ok());
         let response = serde_json::from_str::<TencentCloudResponse<UploadCertificateData>>(&result.unwrap());
@@ -224,11 +1299,12 @@ mod tests {
     async fn test_deploy_certificate() {
         let secret_id = std::env::var("TENCENTCLOUD_SECRET_ID").unwrap();
         let secret_key = std::env::var("TENCENTCLOUD_SECRET_KEY").unwrap();
+        let credential = Credential::new(secret_id, secret_key);
         let certificate_id = "test_cert_id";
         let instance_id_list = vec!["test_instance_id".to_string()];
         let host = "ssl.intl.tencentcloudapi.com";
 
-        let result = deploy_certificate(secret_id, secret_key, certificate_id.to_string(), instance_id_list, host.to_string()).await;
+        let result = deploy_certificate(&credential, certificate_id.to_string(), instance_id_list, "teo", "".to_string(), host.to_string()).await;
         println!("{:?}", result);
         assert!(result.is_ok());
         let response = serde_json::from_str::<TencentCloudResponse<DeployCertificateData>>(&result.unwrap());
@@ -241,10 +1317,11 @@ mod tests {
     async fn test_modify_certificate_notification() {
         let secret_id = std::env::var("TENCENTCLOUD_SECRET_ID").unwrap();
         let secret_key = std::env::var("TENCENTCLOUD_SECRET_KEY").unwrap();
+        let credential = Credential::new(secret_id, secret_key);
         let certificate_id = "test_cert_id";
         let host = "ssl.intl.tencentcloudapi.com";
 
-        let result = modify_certificate_notification(secret_id, secret_key, certificate_id.to_string(), host.to_string()).await;
+        let result = modify_certificate_notification(&credential, certificate_id.to_string(), host.to_string()).await;
         println!("{:?}", result);
         assert!(result.is_ok());
         let response = serde_json::from_str::<TencentCloudResponse<ModifyCertificateNotificationData>>(&result.unwrap());
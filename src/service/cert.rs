@@ -0,0 +1,293 @@
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{Id, PKey};
+use openssl::x509::{X509, X509NameRef, X509VerifyResult};
+
+// 解析 PEM 证书链中的第一张证书，即叶子证书
+pub fn leaf(pem: &str) -> anyhow::Result<X509> {
+    let cert = X509::from_pem(pem.as_bytes())
+        .map_err(|e| anyhow::anyhow!("解析证书失败: {}", e))?;
+    Ok(cert)
+}
+
+// 证书 SHA-256 指纹，小写十六进制，不带冒号
+pub fn fingerprint(cert: &X509) -> anyhow::Result<String> {
+    let digest = cert.digest(MessageDigest::sha256())?;
+    Ok(hex::encode(digest))
+}
+
+// 读取证书文件并计算叶子证书的指纹
+pub fn file_fingerprint(path: &str) -> anyhow::Result<String> {
+    let pem = std::fs::read_to_string(path)?;
+    fingerprint(&leaf(&pem)?)
+}
+
+// 证书的主域名，优先取 CN，没有 CN 时取第一个 DNS SAN
+pub fn primary_domain(cert: &X509) -> Option<String> {
+    let common_name = cert
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().as_utf8().ok())
+        .map(|name| name.to_string());
+    if common_name.is_some() {
+        return common_name;
+    }
+    cert.subject_alt_names()?
+        .iter()
+        .find_map(|name| name.dnsname().map(|dns| dns.to_string()))
+}
+
+// 解析 PEM 证书链，顺序与文件中一致
+pub fn chain(pem: &str) -> anyhow::Result<Vec<X509>> {
+    let chain = X509::stack_from_pem(pem.as_bytes())
+        .map_err(|e| anyhow::anyhow!("解析证书链失败: {}", e))?;
+    if chain.is_empty() {
+        return Err(anyhow::anyhow!("证书文件中没有找到 PEM 格式的证书"));
+    }
+    Ok(chain)
+}
+
+// 证书主体，如 CN=example.com, O=Example
+pub fn subject(cert: &X509) -> String {
+    name_text(cert.subject_name())
+}
+
+// 证书颁发者，如 CN=R3, O=Let's Encrypt, C=US
+pub fn issuer(cert: &X509) -> String {
+    name_text(cert.issuer_name())
+}
+
+fn name_text(name: &X509NameRef) -> String {
+    name.entries()
+        .filter_map(|entry| {
+            let name = entry.object().nid().short_name().ok()?;
+            let value = entry.data().as_utf8().ok()?;
+            Some(format!("{}={}", name, value))
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// 证书的到期时间
+pub fn not_after(cert: &X509) -> anyhow::Result<chrono::DateTime<chrono::Utc>> {
+    let diff = Asn1Time::from_unix(0)?.diff(cert.not_after())?;
+    chrono::DateTime::from_timestamp(diff.days as i64 * 86400 + diff.secs as i64, 0)
+        .ok_or_else(|| anyhow::anyhow!("证书到期时间错误: {}", cert.not_after()))
+}
+
+// 证书 SAN 中的所有 DNS 名称
+pub fn dns_names(cert: &X509) -> Vec<String> {
+    match cert.subject_alt_names() {
+        Some(names) => names.iter().filter_map(|name| name.dnsname().map(|dns| dns.to_lowercase())).collect(),
+        None => vec![],
+    }
+}
+
+// SAN 是否覆盖域名，通配符只匹配一级子域名，如 *.example.com 覆盖 www.example.com 但不覆盖 example.com 和 a.b.example.com
+pub fn san_covers(san: &str, domain: &str) -> bool {
+    let san = san.to_lowercase();
+    let domain = domain.to_lowercase();
+    if san == domain {
+        return true;
+    }
+    match (san.strip_prefix("*."), domain.split_once('.')) {
+        (Some(san_parent), Some((label, domain_parent))) => !label.is_empty() && label != "*" && san_parent == domain_parent,
+        _ => false,
+    }
+}
+
+// 证书的加密算法，与 DescribeCertificates 返回的 EncryptAlgorithm 一致
+pub fn encrypt_algorithm(cert: &X509) -> anyhow::Result<&'static str> {
+    Ok(if cert.public_key()?.id() == Id::EC { "ECC" } else { "RSA" })
+}
+
+// 证书和私钥是否是匹配的一对，文件写入一半或不匹配时返回 false
+pub fn is_matching_pair(certificate_public_key: &str, certificate_private_key: &str) -> bool {
+    let Ok(chain) = chain(certificate_public_key) else {
+        return false;
+    };
+    let Ok(private_key) = PKey::private_key_from_pem(certificate_private_key.as_bytes()) else {
+        return false;
+    };
+    chain[0].public_key().map(|public_key| public_key.public_eq(&private_key)).unwrap_or(false)
+}
+
+// 部署前校验本地证书和私钥，任何一项不通过都返回具体的错误信息
+pub fn validate(certificate_public_key: &str, certificate_private_key: &str, instance_id_list: &[String]) -> anyhow::Result<()> {
+    let chain = chain(certificate_public_key)?;
+    let leaf = &chain[0];
+    let private_key = PKey::private_key_from_pem(certificate_private_key.as_bytes())
+        .map_err(|e| anyhow::anyhow!("解析私钥失败: {}", e))?;
+    if !leaf.public_key()?.public_eq(&private_key) {
+        return Err(anyhow::anyhow!("私钥与证书不匹配"));
+    }
+
+    for (index, pair) in chain.windows(2).enumerate() {
+        let (child, issuer) = (&pair[0], &pair[1]);
+        if issuer.issued(child) != X509VerifyResult::OK || !child.verify(issuer.public_key()?.as_ref())? {
+            return Err(anyhow::anyhow!(
+                "证书链顺序错误，第 {} 张证书不是由第 {} 张证书签发的，证书链应按 叶子证书→中间证书 的顺序排列",
+                index + 1,
+                index + 2
+            ));
+        }
+    }
+
+    let now = Asn1Time::days_from_now(0)?;
+    if leaf.not_before() > now {
+        return Err(anyhow::anyhow!("证书尚未生效，生效时间: {}", leaf.not_before()));
+    }
+    if leaf.not_after() < now {
+        return Err(anyhow::anyhow!("证书已过期，过期时间: {}", leaf.not_after()));
+    }
+
+    let sans = dns_names(leaf);
+    let uncovered: Vec<&String> = instance_id_list
+        .iter()
+        .filter(|domain| !sans.iter().any(|san| san_covers(san, domain)))
+        .collect();
+    if !uncovered.is_empty() {
+        return Err(anyhow::anyhow!("证书不包含以下域名: {:?}，证书 SAN: {:?}", uncovered, sans));
+    }
+    Ok(())
+}
+
+// 校验 RSA + ECC 双证书，两张证书的公钥类型要正确，并且覆盖相同的域名
+pub fn validate_dual(rsa_certificate_public_key: &str, ecc_certificate_public_key: &str) -> anyhow::Result<()> {
+    let rsa = leaf(rsa_certificate_public_key)?;
+    let ecc = leaf(ecc_certificate_public_key)?;
+    if rsa.public_key()?.id() != Id::RSA {
+        return Err(anyhow::anyhow!("RSA 证书的公钥不是 RSA 类型"));
+    }
+    if ecc.public_key()?.id() != Id::EC {
+        return Err(anyhow::anyhow!("ECC 证书的公钥不是 ECDSA 类型"));
+    }
+    let mut rsa_names = dns_names(&rsa);
+    rsa_names.sort();
+    rsa_names.dedup();
+    let mut ecc_names = dns_names(&ecc);
+    ecc_names.sort();
+    ecc_names.dedup();
+    if rsa_names != ecc_names {
+        return Err(anyhow::anyhow!("RSA 证书和 ECC 证书的域名不一致，RSA: {:?}，ECC: {:?}", rsa_names, ecc_names));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+
+    use crate::service::test_util::{self_signed, signed, to_pem};
+
+    #[test]
+    fn test_fingerprint() {
+        let (cert, _) = self_signed("example.com", &["example.com"], 1, 30);
+        let pem = String::from_utf8(cert.to_pem().unwrap()).unwrap();
+        let fingerprint = super::fingerprint(&super::leaf(&pem).unwrap()).unwrap();
+        assert_eq!(fingerprint.len(), 64);
+        assert_eq!(fingerprint, hex::encode(cert.digest(MessageDigest::sha256()).unwrap()));
+    }
+
+    #[test]
+    fn test_primary_domain() {
+        let (cert, _) = self_signed("example.com", &["www.example.com"], 1, 30);
+        assert_eq!(super::primary_domain(&cert), Some("example.com".to_string()));
+    }
+
+    #[test]
+    fn test_subject() {
+        let (cert, _) = self_signed("example.com", &["example.com"], 1, 30);
+        assert_eq!(super::subject(&cert), "CN=example.com");
+        let (ca, ca_key) = self_signed("Test CA", &[], 1, 30);
+        let (_, key) = self_signed("example.com", &["example.com"], 1, 30);
+        let cert = signed("example.com", &["example.com"], 1, 30, &key, Some((&ca, &ca_key)));
+        assert_eq!(super::issuer(&cert), "CN=Test CA");
+    }
+
+    #[test]
+    fn test_not_after() {
+        let (cert, _) = self_signed("example.com", &["example.com"], 1, 30);
+        let hours = (super::not_after(&cert).unwrap() - chrono::Utc::now()).num_hours();
+        assert!((29 * 24..=30 * 24).contains(&hours));
+    }
+
+    #[test]
+    fn test_san_covers() {
+        assert!(super::san_covers("example.com", "Example.com"));
+        assert!(super::san_covers("*.example.com", "www.example.com"));
+        assert!(super::san_covers("*.example.com", "*.example.com"));
+        assert!(!super::san_covers("*.example.com", "example.com"));
+        assert!(!super::san_covers("*.example.com", "a.b.example.com"));
+        assert!(!super::san_covers("www.example.com", "*.example.com"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let domains = vec!["example.com".to_string(), "www.example.com".to_string()];
+        let (cert, key) = self_signed("example.com", &["example.com", "*.example.com"], 1, 30);
+        let (public_key, private_key) = to_pem(&[&cert], &key);
+        assert!(super::validate(&public_key, &private_key, &domains).is_ok());
+
+        let (_, other_key) = self_signed("example.com", &["example.com"], 1, 30);
+        let (_, other_private_key) = to_pem(&[&cert], &other_key);
+        let error = super::validate(&public_key, &other_private_key, &domains).unwrap_err();
+        assert!(error.to_string().contains("私钥与证书不匹配"));
+
+        let error = super::validate(&public_key, &private_key, &["a.b.example.com".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("a.b.example.com"));
+
+        let (expired, expired_key) = self_signed("example.com", &["example.com"], 30, -1);
+        let (public_key, private_key) = to_pem(&[&expired], &expired_key);
+        let error = super::validate(&public_key, &private_key, &[]).unwrap_err();
+        assert!(error.to_string().contains("证书已过期"));
+
+        let (not_yet_valid, not_yet_valid_key) = self_signed("example.com", &["example.com"], -1, 30);
+        let (public_key, private_key) = to_pem(&[&not_yet_valid], &not_yet_valid_key);
+        let error = super::validate(&public_key, &private_key, &[]).unwrap_err();
+        assert!(error.to_string().contains("证书尚未生效"));
+    }
+
+    #[test]
+    fn test_validate_chain_order() {
+        let (ca, ca_key) = self_signed("Test CA", &[], 1, 365);
+        let leaf_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let leaf = signed("example.com", &["example.com"], 1, 30, &leaf_key, Some((&ca, &ca_key)));
+
+        let (public_key, private_key) = to_pem(&[&leaf, &ca], &leaf_key);
+        assert!(super::validate(&public_key, &private_key, &["example.com".to_string()]).is_ok());
+
+        let (public_key, private_key) = to_pem(&[&ca, &leaf], &leaf_key);
+        let error = super::validate(&public_key, &private_key, &["example.com".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("私钥与证书不匹配"));
+
+        let (other_ca, _) = self_signed("Other CA", &[], 1, 365);
+        let (public_key, private_key) = to_pem(&[&leaf, &other_ca], &leaf_key);
+        let error = super::validate(&public_key, &private_key, &["example.com".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("证书链顺序错误"));
+    }
+
+    #[test]
+    fn test_validate_dual() {
+        let (rsa, rsa_key) = self_signed("example.com", &["example.com", "*.example.com"], 1, 30);
+        let ecc_key = PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap()).unwrap();
+        let ecc = signed("example.com", &["*.example.com", "example.com"], 1, 30, &ecc_key, None);
+        let (rsa_public_key, _) = to_pem(&[&rsa], &rsa_key);
+        let (ecc_public_key, _) = to_pem(&[&ecc], &ecc_key);
+        assert!(super::validate_dual(&rsa_public_key, &ecc_public_key).is_ok());
+
+        let error = super::validate_dual(&ecc_public_key, &rsa_public_key).unwrap_err();
+        assert!(error.to_string().contains("RSA 证书的公钥不是 RSA 类型"));
+
+        let other = signed("example.com", &["example.com"], 1, 30, &ecc_key, None);
+        let (other_public_key, _) = to_pem(&[&other], &ecc_key);
+        let error = super::validate_dual(&rsa_public_key, &other_public_key).unwrap_err();
+        assert!(error.to_string().contains("域名不一致"));
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// 证书检查中的一个证书，本地证书文件或 EO 域名绑定的证书
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckedCertificate {
+    // 本地证书为文件路径，EO 绑定的证书为域名
+    pub name: String,
+    pub certificate_id: Option<String>,
+    pub fingerprint: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl CheckedCertificate {
+    pub fn from_pem(name: &str, certificate_id: Option<&str>, pem: &str) -> anyhow::Result<Self> {
+        let cert = super::cert::leaf(pem)?;
+        Ok(Self {
+            name: name.to_string(),
+            certificate_id: certificate_id.map(|certificate_id| certificate_id.to_string()),
+            fingerprint: super::cert::fingerprint(&cert)?,
+            issuer: super::cert::issuer(&cert),
+            sans: super::cert::dns_names(&cert),
+            not_after: super::cert::not_after(&cert)?,
+        })
+    }
+
+    pub fn days_remaining(&self, now: DateTime<Utc>) -> i64 {
+        (self.not_after - now).num_days()
+    }
+
+    // 已过期或剩余天数不超过 warning_days 时返回问题描述
+    pub fn expiry_problem(&self, warning_days: i64, now: DateTime<Utc>) -> Option<String> {
+        let not_after = self.not_after.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+        let certificate = match &self.certificate_id {
+            Some(certificate_id) => format!("{} 的证书 {}", self.name, certificate_id),
+            None => format!("证书 {}", self.name),
+        };
+        if self.not_after <= now {
+            return Some(format!("{} 已于 {} 过期", certificate, not_after));
+        }
+        let days_remaining = self.days_remaining(now);
+        if days_remaining <= warning_days {
+            return Some(format!("{} 将于 {} 到期，剩余 {} 天", certificate, not_after, days_remaining));
+        }
+        None
+    }
+}
+
+// 域名绑定的证书与本地证书不一致时返回问题描述，同时部署 RSA 和 ECC 证书时要求两者都已绑定
+pub fn mismatch_problem(domain: &str, local: &[CheckedCertificate], bound: &[CheckedCertificate]) -> Option<String> {
+    let missing: Vec<&str> = local
+        .iter()
+        .filter(|local| !bound.iter().any(|bound| bound.fingerprint == local.fingerprint))
+        .map(|local| local.name.as_str())
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+    let bound_ids: Vec<&str> = bound.iter().filter_map(|bound| bound.certificate_id.as_deref()).collect();
+    let bound_ids = if bound_ids.is_empty() { "无".to_string() } else { bound_ids.join(", ") };
+    Some(format!("域名 {} 绑定的证书 {} 与本地证书 {} 不一致", domain, bound_ids, missing.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::test_util::{self_signed, to_pem};
+
+    fn checked(name: &str, certificate_id: Option<&str>, days_after: i64) -> CheckedCertificate {
+        let (cert, key) = self_signed("example.com", &["example.com"], 1, days_after);
+        let (pem, _) = to_pem(&[&cert], &key);
+        CheckedCertificate::from_pem(name, certificate_id, &pem).unwrap()
+    }
+
+    // 检查时间都按证书的到期时间推算，不受生成证书耗时的影响
+    #[test]
+    fn test_expiry_problem() {
+        let certificate = checked("cert.pem", None, 60);
+        let not_after = certificate.not_after;
+        assert_eq!(certificate.expiry_problem(14, not_after - chrono::Duration::days(15)), None);
+        let problem = certificate.expiry_problem(14, not_after - chrono::Duration::days(9) - chrono::Duration::hours(1)).unwrap();
+        assert!(problem.starts_with("证书 cert.pem 将于 ") && problem.ends_with("剩余 9 天"));
+        let certificate = CheckedCertificate { name: "example.com".to_string(), certificate_id: Some("abc".to_string()), ..certificate };
+        let problem = certificate.expiry_problem(14, not_after).unwrap();
+        assert!(problem.starts_with("example.com 的证书 abc 已于 ") && problem.ends_with("过期"));
+    }
+
+    #[test]
+    fn test_mismatch_problem() {
+        let rsa = checked("rsa.pem", None, 60);
+        let ecc = checked("ecc.pem", None, 60);
+        let bound = CheckedCertificate { name: "example.com".to_string(), certificate_id: Some("abc".to_string()), ..rsa.clone() };
+        assert_eq!(mismatch_problem("example.com", std::slice::from_ref(&rsa), std::slice::from_ref(&bound)), None);
+        assert_eq!(
+            mismatch_problem("example.com", &[rsa, ecc], &[bound]),
+            Some("域名 example.com 绑定的证书 abc 与本地证书 ecc.pem 不一致".to_string())
+        );
+        assert_eq!(
+            mismatch_problem("example.com", &[checked("rsa.pem", None, 60)], &[]),
+            Some("域名 example.com 绑定的证书 无 与本地证书 rsa.pem 不一致".to_string())
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use super::credential::CredentialProvider;
+use super::ssl_api::*;
+use super::tc_request::TencentCloudResponse;
+
+// 被当前证书取代的旧证书：SAN 和加密算法相同、到期时间不晚于当前证书的其他证书，保留到期时间最晚的 keep 个，
+// protected 中的证书（回滚的目标）不清理，也不占 keep 的名额，
+// 证书列表中找不到当前证书或到期时间无法解析时不清理，避免把比当前证书更新的证书也当作旧证书
+pub fn superseded_certificates(
+    certificates: &[CertificateItem],
+    certificate_id: &str,
+    protected: &[String],
+    sans: &[String],
+    encrypt_algorithm: &str,
+    keep: usize,
+) -> Vec<String> {
+    let Some(current_end_time) = certificates
+        .iter()
+        .find(|certificate| certificate.certificate_id == certificate_id)
+        .and_then(|certificate| certificate.end_time())
+    else {
+        tracing::warn!("证书列表中没有找到当前证书 {} 的到期时间，不清理旧证书", certificate_id);
+        return vec![];
+    };
+    super::replace::same_domain_certificates(certificates, certificate_id, sans, encrypt_algorithm)
+        .into_iter()
+        .filter(|certificate| certificate.end_time().is_some_and(|end_time| end_time <= current_end_time))
+        .filter(|certificate| !protected.contains(&certificate.certificate_id))
+        .skip(keep)
+        .map(|certificate| certificate.certificate_id.clone())
+        .collect()
+}
+
+// 查询证书关联的云资源，返回 CertificateId -> 关联的资源类型和数量，查询失败的证书不返回
+async fn bound_resources(
+    credentials: &CredentialProvider,
+    certificate_ids: &[String],
+    host: &str,
+) -> anyhow::Result<Vec<(String, Vec<(String, i64)>)>> {
+    let create_param = create_certificate_bind_resource_sync_task(&credentials.get().await?, certificate_ids.to_vec(), host.to_string()).await?;
+    let create_response = serde_json::from_str::<TencentCloudResponse<CreateCertificateBindResourceSyncTaskData>>(&create_param)?;
+    if let Some(error) = create_response.response.error {
+        return Err(error.with_request_id(&create_response.response.request_id).into());
+    }
+    let cert_task_ids = create_response.response.data.map(|data| data.cert_task_ids).unwrap_or_default();
+    let task_ids: Vec<String> = cert_task_ids.iter().map(|cert_task_id| cert_task_id.task_id.clone()).collect();
+
+    for _ in 0..30 {
+        let result_param = describe_certificate_bind_resource_task_result(&credentials.get().await?, task_ids.clone(), host.to_string()).await?;
+        let result_response = serde_json::from_str::<TencentCloudResponse<DescribeCertificateBindResourceTaskResultData>>(&result_param)?;
+        if let Some(error) = result_response.response.error {
+            return Err(error.with_request_id(&result_response.response.request_id).into());
+        }
+        let results = result_response.response.data.and_then(|data| data.sync_task_bind_resource_result).unwrap_or_default();
+        if results.len() == task_ids.len() && results.iter().all(|result| result.status != 0) {
+            let mut bound = vec![];
+            for result in results {
+                let Some(cert_task_id) = cert_task_ids.iter().find(|cert_task_id| cert_task_id.task_id == result.task_id) else {
+                    continue;
+                };
+                if result.status != 1 {
+                    tracing::warn!("查询证书 {} 关联的云资源失败，不删除", cert_task_id.cert_id);
+                    continue;
+                }
+                bound.push((cert_task_id.cert_id.clone(), result.bound_resources()));
+            }
+            return Ok(bound);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+    Err(anyhow::anyhow!("查询证书关联的云资源超时"))
+}
+
+// 清理被当前证书取代并且没有关联任何云资源的已上传证书，dry_run 时只输出要删除的证书，返回删除（或将要删除）的证书
+pub async fn cleanup(
+    credentials: &CredentialProvider,
+    certificate_public_key: &str,
+    certificate_id: &str,
+    protected: &[String],
+    keep: usize,
+    dry_run: bool,
+    host: &str,
+) -> anyhow::Result<Vec<String>> {
+    let leaf = super::cert::leaf(certificate_public_key)?;
+    let Some(domain) = super::cert::primary_domain(&leaf) else {
+        return Ok(vec![]);
+    };
+    // 只清理上传的证书，不会删除购买或申请的证书
+    let filter = CertificateFilter {
+        search_key: Some(domain),
+        upload_only: true,
+        ..Default::default()
+    };
+    let certificates = super::replace::describe_all_certificates(credentials, &filter, host).await?;
+    let superseded = superseded_certificates(
+        &certificates,
+        certificate_id,
+        protected,
+        &super::cert::dns_names(&leaf),
+        super::cert::encrypt_algorithm(&leaf)?,
+        keep,
+    );
+    if superseded.is_empty() {
+        tracing::info!("没有被证书 {} 取代的旧证书", certificate_id);
+        return Ok(vec![]);
+    }
+
+    let mut removed = vec![];
+    for (old_certificate_id, resources) in bound_resources(credentials, &superseded, host).await? {
+        if !resources.is_empty() {
+            tracing::info!("旧证书 {} 仍关联云资源 {:?}，不删除", old_certificate_id, resources);
+            continue;
+        }
+        if dry_run {
+            tracing::info!("将删除旧证书 {}", old_certificate_id);
+            removed.push(old_certificate_id);
+            continue;
+        }
+        let delete_param = delete_certificate(&credentials.get().await?, old_certificate_id.clone(), host.to_string()).await?;
+        let delete_response = serde_json::from_str::<TencentCloudResponse<DeleteCertificateData>>(&delete_param)?;
+        if let Some(error) = delete_response.response.error {
+            tracing::warn!("删除旧证书 {} 失败: {}", old_certificate_id, error);
+            continue;
+        }
+        tracing::info!("已删除旧证书 {}", old_certificate_id);
+        removed.push(old_certificate_id);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_superseded_certificates() {
+        let certificates = serde_json::from_str::<Vec<CertificateItem>>(r#"[
+            {"CertificateId": "current", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2027-01-01 00:00:00"},
+            {"CertificateId": "newer", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2027-02-01 00:00:00"},
+            {"CertificateId": "old", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-10-01 00:00:00"},
+            {"CertificateId": "older", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-07-01 00:00:00"},
+            {"CertificateId": "ecc", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "ECC", "CertEndTime": "2026-07-01 00:00:00"}
+        ]"#).unwrap();
+        let sans = vec!["example.com".to_string()];
+
+        assert_eq!(superseded_certificates(&certificates, "current", &[], &sans, "RSA", 0), vec!["old", "older"]);
+        assert_eq!(superseded_certificates(&certificates, "current", &[], &sans, "RSA", 1), vec!["older"]);
+        assert!(superseded_certificates(&certificates, "current", &[], &sans, "RSA", 2).is_empty());
+        // 回滚目标 old 不清理
+        assert_eq!(superseded_certificates(&certificates, "current", &["old".to_string()], &sans, "RSA", 0), vec!["older"]);
+        assert!(superseded_certificates(&certificates, "current", &["old".to_string()], &sans, "RSA", 1).is_empty());
+        // 列表中没有当前证书时，不能把 newer 也当作旧证书
+        assert!(superseded_certificates(&certificates, "missing", &[], &sans, "RSA", 0).is_empty());
+    }
+
+    #[test]
+    fn test_end_time_order() {
+        let certificates = serde_json::from_str::<Vec<CertificateItem>>(r#"[
+            {"CertificateId": "current", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2027-01-01 00:00:00"},
+            {"CertificateId": "late", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-12-31 23:00:00"},
+            {"CertificateId": "early", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-9-01 00:00:00"},
+            {"CertificateId": "unknown", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "unknown"}
+        ]"#).unwrap();
+        let sans = vec!["example.com".to_string()];
+        // 按时间而不是字符串比较，2026-9-01 早于 2026-12-31，无法解析到期时间的证书不清理
+        assert_eq!(superseded_certificates(&certificates, "current", &[], &sans, "RSA", 0), vec!["late", "early"]);
+    }
+}
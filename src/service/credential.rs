@@ -0,0 +1,355 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::args_conf::Args;
+use super::secret::Secret;
+use super::tc_request::{TencentCloudRequest, TencentCloudResponse};
+
+const CVM_ROLE_URL: &str = "http://metadata.tencentyun.com/latest/meta-data/cam/security-credentials/";
+
+// 腾讯云密钥，临时密钥带有 token 和过期时间
+#[derive(Clone)]
+pub struct Credential {
+    pub secret_id: Secret<String>,
+    pub secret_key: Secret<String>,
+    pub token: Secret<String>,
+    pub expired_time: Option<i64>,
+}
+
+impl Credential {
+    pub fn new(secret_id: String, secret_key: String) -> Self {
+        Self {
+            secret_id: Secret::new(secret_id),
+            secret_key: Secret::new(secret_key),
+            token: Secret::default(),
+            expired_time: None,
+        }
+    }
+
+    // 临时密钥在过期前 5 分钟就视为需要刷新
+    pub fn is_expiring(&self) -> bool {
+        match self.expired_time {
+            Some(expired_time) => chrono::Utc::now().timestamp() + 300 >= expired_time,
+            None => false,
+        }
+    }
+}
+
+// 按顺序从各个来源获取密钥，并缓存临时密钥直到快过期
+pub struct CredentialProvider {
+    args: Args,
+    cached: tokio::sync::Mutex<Option<Credential>>,
+}
+
+impl CredentialProvider {
+    pub fn new(args: &Args) -> Self {
+        Self {
+            args: args.clone(),
+            cached: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub async fn get(&self) -> anyhow::Result<Credential> {
+        let mut cached = self.cached.lock().await;
+        if let Some(credential) = cached.as_ref() && !credential.is_expiring() {
+            return Ok(credential.clone());
+        }
+        let credential = resolve(&self.args).await?;
+        *cached = Some(credential.clone());
+        Ok(credential)
+    }
+}
+
+// 配置了 OIDC 时用 OIDC 令牌扮演角色，否则获取基础密钥，配置了角色 ARN 时再用基础密钥扮演角色获取临时密钥
+async fn resolve(args: &Args) -> anyhow::Result<Credential> {
+    if let Some(provider_id) = &args.oidc_provider_id {
+        let role_arn = args.role_arn.as_ref().ok_or(anyhow::anyhow!("使用 OIDC 时必须设置 --role-arn 或环境变量 TENCENTCLOUD_ROLE_ARN"))?;
+        let token = web_identity_token(args)?;
+        let role_session_name = args.role_session_name.clone().unwrap_or("tc-eo-ssl".to_string());
+        tracing::info!("正在使用 OIDC 令牌扮演角色 {} ...", role_arn);
+        return assume_role_with_web_identity(provider_id, &token, role_arn, &role_session_name, args.intl.unwrap_or(false)).await;
+    }
+
+    let credential = base_credential(args).await?;
+    match &args.role_arn {
+        Some(role_arn) => {
+            let role_session_name = args.role_session_name.clone().unwrap_or("tc-eo-ssl".to_string());
+            tracing::info!("正在扮演角色 {} ...", role_arn);
+            assume_role(&credential, role_arn, &role_session_name, args.intl.unwrap_or(false)).await
+        },
+        None => Ok(credential),
+    }
+}
+
+// 密钥来源顺序：命令行参数/环境变量/配置文件 -> ~/.tencentcloud/credentials -> CVM 实例角色
+async fn base_credential(args: &Args) -> anyhow::Result<Credential> {
+    if let (Some(secret_id), Some(secret_key)) = (&args.secret_id, &args.secret_key) {
+        return Ok(Credential {
+            secret_id: secret_id.clone(),
+            secret_key: secret_key.clone(),
+            token: args.token.clone().unwrap_or_default(),
+            expired_time: None,
+        });
+    }
+
+    let profile = args.profile.clone().unwrap_or("default".to_string());
+    if let Some(credential) = profile_credential(&profile)? {
+        tracing::info!("使用 ~/.tencentcloud/credentials 中 {} 的密钥", profile);
+        return Ok(credential);
+    }
+
+    if let Some(credential) = cvm_role_credential().await? {
+        tracing::info!("使用 CVM 实例角色的临时密钥");
+        return Ok(credential);
+    }
+
+    Err(anyhow::anyhow!(
+        "无法获取腾讯云密钥，请设置 --secret-id/--secret-key、环境变量 TENCENTCLOUD_SECRET_ID/TENCENTCLOUD_SECRET_KEY、~/.tencentcloud/credentials 或在 CVM 上绑定实例角色"
+    ))
+}
+
+// 读取 tccli 格式的 ~/.tencentcloud/credentials
+fn profile_credential(profile: &str) -> anyhow::Result<Option<Credential>> {
+    let Ok(home) = std::env::var("HOME") else {
+        return Ok(None);
+    };
+    let path = std::path::Path::new(&home).join(".tencentcloud").join("credentials");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(anyhow::anyhow!("读取 {} 失败: {}", path.display(), e)),
+    };
+    Ok(parse_profile(&content, profile))
+}
+
+fn parse_profile(content: &str, profile: &str) -> Option<Credential> {
+    let mut section = "";
+    let mut secret_id = None;
+    let mut secret_key = None;
+    let mut token = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.trim();
+            continue;
+        }
+        if section != profile {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let value = Some(value.trim().to_string());
+            match key.trim() {
+                "secret_id" => secret_id = value,
+                "secret_key" => secret_key = value,
+                "token" => token = value,
+                _ => (),
+            }
+        }
+    }
+    let mut credential = Credential::new(secret_id?, secret_key?);
+    credential.token = Secret::new(token.unwrap_or_default());
+    Some(credential)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CvmRoleCredential {
+    #[serde(rename = "TmpSecretId")]
+    tmp_secret_id: String,
+    #[serde(rename = "TmpSecretKey")]
+    tmp_secret_key: String,
+    #[serde(rename = "Token")]
+    token: String,
+    #[serde(rename = "ExpiredTime")]
+    expired_time: i64,
+}
+
+// 从 CVM 元数据服务获取实例角色的临时密钥，不在 CVM 上运行时返回 None
+async fn cvm_role_credential() -> anyhow::Result<Option<Credential>> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(2))
+        .build()?;
+    let Ok(response) = client.get(CVM_ROLE_URL).send().await else {
+        return Ok(None);
+    };
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+    let role_name = response.text().await?.lines().next().unwrap_or("").trim().to_string();
+    if role_name.is_empty() {
+        return Ok(None);
+    }
+    let response = client.get(format!("{}{}", CVM_ROLE_URL, role_name)).send().await?;
+    let role_credential = response.json::<CvmRoleCredential>().await
+        .map_err(|e| anyhow::anyhow!("解析 CVM 实例角色 {} 的临时密钥失败: {}", role_name, e))?;
+    Ok(Some(Credential {
+        secret_id: Secret::new(role_credential.tmp_secret_id),
+        secret_key: Secret::new(role_credential.tmp_secret_key),
+        token: Secret::new(role_credential.token),
+        expired_time: Some(role_credential.expired_time),
+    }))
+}
+
+// STS 临时密钥响应数据结构
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StsCredentialData {
+    #[serde(rename = "Credentials")]
+    pub credentials: StsCredentials,
+    #[serde(rename = "ExpiredTime")]
+    pub expired_time: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StsCredentials {
+    #[serde(rename = "Token")]
+    pub token: String,
+    #[serde(rename = "TmpSecretId")]
+    pub tmp_secret_id: String,
+    #[serde(rename = "TmpSecretKey")]
+    pub tmp_secret_key: String,
+}
+
+impl StsCredentialData {
+    pub fn into_credential(self) -> Credential {
+        Credential {
+            secret_id: Secret::new(self.credentials.tmp_secret_id),
+            secret_key: Secret::new(self.credentials.tmp_secret_key),
+            token: Secret::new(self.credentials.token),
+            expired_time: Some(self.expired_time),
+        }
+    }
+}
+
+pub fn sts_host(intl: bool) -> String {
+    if intl { "sts.intl.tencentcloudapi.com".to_string() } else { "sts.tencentcloudapi.com".to_string() }
+}
+
+// 使用 STS AssumeRole 扮演角色获取临时密钥
+async fn assume_role(credential: &Credential, role_arn: &str, role_session_name: &str, intl: bool) -> anyhow::Result<Credential> {
+    let payload = serde_json::json!({
+        "RoleArn": role_arn,
+        "RoleSessionName": role_session_name,
+        "DurationSeconds": 7200
+    })
+    .to_string();
+
+    let request = TencentCloudRequest::new(
+        credential.secret_id.clone(),
+        credential.secret_key.clone(),
+        "sts".to_string(),
+        sts_host(intl),
+        "ap-guangzhou".to_string(),
+        "AssumeRole".to_string(),
+        "2018-08-13".to_string(),
+        payload,
+        credential.token.clone(),
+    );
+
+    let response = request.send().await?;
+    let response = serde_json::from_str::<TencentCloudResponse<StsCredentialData>>(&response)?;
+    if let Some(error) = response.response.error {
+        return Err(anyhow::anyhow!("扮演角色 {} 失败: {}", role_arn, error));
+    }
+    let data = response.response.data.ok_or(anyhow::anyhow!("扮演角色 {} 失败: 响应中没有临时密钥", role_arn))?;
+    Ok(data.into_credential())
+}
+
+// OIDC 令牌优先从文件读取，CI 中令牌文件可能会被轮换
+fn web_identity_token(args: &Args) -> anyhow::Result<String> {
+    if let Some(path) = &args.web_identity_token_file {
+        let token = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("读取 OIDC 令牌文件 {} 失败: {}", path, e))?;
+        return Ok(token.trim().to_string());
+    }
+    args.web_identity_token.as_ref().map(|token| token.expose().clone()).ok_or(anyhow::anyhow!(
+        "使用 OIDC 时必须设置 --web-identity-token-file 或 --web-identity-token，或环境变量 TENCENTCLOUD_WEB_IDENTITY_TOKEN_FILE、TENCENTCLOUD_WEB_IDENTITY_TOKEN"
+    ))
+}
+
+// 使用 STS AssumeRoleWithWebIdentity 以 OIDC 令牌扮演角色获取临时密钥，该接口不需要签名
+async fn assume_role_with_web_identity(
+    provider_id: &str,
+    web_identity_token: &str,
+    role_arn: &str,
+    role_session_name: &str,
+    intl: bool,
+) -> anyhow::Result<Credential> {
+    let payload = serde_json::json!({
+        "ProviderId": provider_id,
+        "WebIdentityToken": web_identity_token,
+        "RoleArn": role_arn,
+        "RoleSessionName": role_session_name,
+        "DurationSeconds": 7200
+    })
+    .to_string();
+
+    let request = TencentCloudRequest::new(
+        Secret::default(),
+        Secret::default(),
+        "sts".to_string(),
+        sts_host(intl),
+        "ap-guangzhou".to_string(),
+        "AssumeRoleWithWebIdentity".to_string(),
+        "2018-08-13".to_string(),
+        payload,
+        Secret::default(),
+    );
+
+    let response = request.send_unsigned().await?;
+    let response = serde_json::from_str::<TencentCloudResponse<StsCredentialData>>(&response)?;
+    if let Some(error) = response.response.error {
+        return Err(anyhow::anyhow!("使用 OIDC 令牌扮演角色 {} 失败: {}", role_arn, error));
+    }
+    let data = response.response.data.ok_or(anyhow::anyhow!("使用 OIDC 令牌扮演角色 {} 失败: 响应中没有临时密钥", role_arn))?;
+    Ok(data.into_credential())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile() {
+        let content = r#"
+            [default]
+            secret_id = default_id
+            secret_key = default_key
+
+            [prod]
+            secret_id = prod_id
+            secret_key = prod_key
+            token = prod_token
+        "#;
+        let credential = parse_profile(content, "default").unwrap();
+        assert_eq!(credential.secret_id.expose(), "default_id");
+        assert_eq!(credential.secret_key.expose(), "default_key");
+        assert_eq!(credential.token.expose(), "");
+        let credential = parse_profile(content, "prod").unwrap();
+        assert_eq!(credential.secret_id.expose(), "prod_id");
+        assert_eq!(credential.token.expose(), "prod_token");
+        assert!(parse_profile(content, "missing").is_none());
+    }
+
+    #[test]
+    fn test_web_identity_token() {
+        let path = std::env::temp_dir().join(format!("tc-eo-ssl-oidc-{}", std::process::id()));
+        std::fs::write(&path, "file_token\n").unwrap();
+        let mut args = Args { web_identity_token: Some(Secret::new("env_token".to_string())), ..Default::default() };
+        assert_eq!(web_identity_token(&args).unwrap(), "env_token");
+        args.web_identity_token_file = Some(path.to_string_lossy().to_string());
+        assert_eq!(web_identity_token(&args).unwrap(), "file_token");
+        std::fs::remove_file(&path).unwrap();
+        assert!(web_identity_token(&Args::default()).is_err());
+    }
+
+    #[test]
+    fn test_is_expiring() {
+        let mut credential = Credential::new("id".to_string(), "key".to_string());
+        assert!(!credential.is_expiring());
+        credential.expired_time = Some(chrono::Utc::now().timestamp() + 60);
+        assert!(credential.is_expiring());
+        credential.expired_time = Some(chrono::Utc::now().timestamp() + 3600);
+        assert!(!credential.is_expiring());
+    }
+}
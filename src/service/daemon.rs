@@ -0,0 +1,80 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::Local;
+use cron::Schedule;
+
+// 解析 cron 表达式，支持标准的 5 段格式（分 时 日 月 周），也支持带秒的 6 段或 7 段格式
+pub fn parse_schedule(expression: &str) -> anyhow::Result<Schedule> {
+    let expression = expression.trim();
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    Schedule::from_str(&expression).map_err(|e| anyhow::anyhow!("解析 cron 表达式 {} 失败: {}", expression, e))
+}
+
+// SIGTERM 信号，非 unix 平台没有 SIGTERM，recv 永远不会返回，只能通过 Ctrl+C 退出
+struct Terminate {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+impl Terminate {
+    fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+// 按 cron 计划定时执行 on_tick，每次执行时间增加 0 到 jitter 之间的随机延迟
+// 收到 SIGTERM 或 Ctrl+C 后退出，正在执行的 on_tick 会执行完成后再退出
+pub async fn run(schedule: &Schedule, jitter: Duration, mut on_tick: impl AsyncFnMut()) -> anyhow::Result<()> {
+    let mut sigterm = Terminate::new()?;
+    loop {
+        let Some(next) = schedule.upcoming(Local).next() else {
+            return Err(anyhow::anyhow!("cron 表达式没有下一次执行时间"));
+        };
+        let delay = Duration::from_secs(rand::random_range(0..=jitter.as_secs()));
+        let run_at = next + chrono::Duration::from_std(delay)?;
+        tracing::info!("下次执行时间: {}", run_at.format("%Y-%m-%d %H:%M:%S %:z"));
+        let wait = (run_at - Local::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {},
+            _ = sigterm.recv() => {
+                tracing::info!("收到 SIGTERM 信号，退出");
+                return Ok(());
+            },
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("收到 Ctrl+C 信号，退出");
+                return Ok(());
+            },
+        }
+        on_tick().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_schedule() {
+        assert!(parse_schedule("0 3 * * *").is_ok());
+        assert!(parse_schedule("30 0 3 * * *").is_ok());
+        assert!(parse_schedule("0 3 * *").is_err());
+        let schedule = parse_schedule("15 3 * * *").unwrap();
+        let next = schedule.upcoming(Local).next().unwrap();
+        assert_eq!(next.format("%H:%M:%S").to_string(), "03:15:00");
+    }
+}
@@ -0,0 +1,81 @@
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+
+use super::notify::{Message, Notifier, SendFuture};
+use super::secret::Secret;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 钉钉群机器人，配置了加签密钥时在 webhook 地址后附加 timestamp 和 sign
+pub struct DingTalk {
+    webhook_url: Secret<String>,
+    secret: Option<Secret<String>>,
+}
+
+impl DingTalk {
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        Some(Self {
+            webhook_url: config.dingtalk_webhook_url.clone()?,
+            secret: config.dingtalk_secret.clone(),
+        })
+    }
+}
+
+// 加签：以密钥为 key 对 "毫秒时间戳\n密钥" 做 HmacSHA256，再 base64
+pub fn sign(secret: &str, timestamp: i64) -> anyhow::Result<String> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(format!("{}\n{}", timestamp, secret).as_bytes());
+    Ok(openssl::base64::encode_block(&mac.finalize().into_bytes()))
+}
+
+// 钉钉的 markdown 单个换行不会换行，正文每行之间用空行分段
+pub fn format(message: &Message) -> String {
+    let mut text = format!("#### {}{}", message.icon(), message.title);
+    for line in message.body.lines() {
+        text.push_str(&format!("\n\n{}", line));
+    }
+    text
+}
+
+impl Notifier for DingTalk {
+    fn name(&self) -> &'static str {
+        "dingtalk"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let mut url = reqwest::Url::parse(self.webhook_url.expose())?;
+            if let Some(secret) = &self.secret {
+                let timestamp = chrono::Utc::now().timestamp_millis();
+                url.query_pairs_mut()
+                    .append_pair("timestamp", &timestamp.to_string())
+                    .append_pair("sign", &sign(secret.expose(), timestamp)?);
+            }
+            let body = json!({"msgtype": "markdown", "markdown": {"title": message.title, "text": format(message)}});
+            let response = super::notify::post_json(url.as_str(), &body).await?;
+            // 成功时 errcode 为 0
+            if response["errcode"].as_i64() != Some(0) {
+                return Err(anyhow::anyhow!("{}", response));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::notify::Event;
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(sign("SECxxx", 1700000000000).unwrap(), "plK5HYD7pW0AMQz3PBPzNXBlZe9ZIHa2a52gMYB3lHs=");
+    }
+
+    #[test]
+    fn test_format() {
+        let message = Message::new("证书部署成功\n域名: www.example.com\n证书ID: abc", &Event::default().success());
+        assert_eq!(format(&message), "#### ✅证书部署成功\n\n域名: www.example.com\n\n证书ID: abc");
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::args_conf::Args;
+
+use super::credential::CredentialProvider;
+use super::tc_request::TencentCloudResponse;
+use super::teo_api::*;
+
+// 通配符匹配，* 匹配任意个字符（包括 .），? 匹配一个字符，不区分大小写
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 * 的位置，以及它匹配到的文本位置，失配时回溯到这里让 * 多匹配一个字符
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// 从加速域名中选出被证书 SAN 覆盖的域名，设置了 include 时只保留匹配任一 include 的域名，再排除匹配任一 exclude 的域名
+pub fn select(domains: &[String], sans: &[String], include: &[String], exclude: &[String]) -> Vec<String> {
+    let mut selected: Vec<String> = domains
+        .iter()
+        .filter(|domain| sans.iter().any(|san| super::cert::san_covers(san, domain)))
+        .filter(|domain| include.is_empty() || include.iter().any(|pattern| glob_matches(pattern, domain)))
+        .filter(|domain| !exclude.iter().any(|pattern| glob_matches(pattern, domain)))
+        .cloned()
+        .collect();
+    selected.sort();
+    selected.dedup();
+    selected
+}
+
+// 查询账号下所有站点的加速域名
+pub async fn acceleration_domains(credentials: &CredentialProvider, host: &str) -> anyhow::Result<Vec<AccelerationDomain>> {
+    let limit = 100;
+    let mut zones = vec![];
+    loop {
+        let zones_param = describe_zones(&credentials.get().await?, zones.len() as i64, limit, host.to_string()).await?;
+        let zones_response = serde_json::from_str::<TencentCloudResponse<DescribeZonesData>>(&zones_param)?;
+        if let Some(error) = zones_response.response.error {
+            return Err(error.with_request_id(&zones_response.response.request_id).into());
+        }
+        let Some(data) = zones_response.response.data else {
+            break;
+        };
+        let count = data.zones.len();
+        zones.extend(data.zones);
+        if count == 0 || zones.len() as i64 >= data.total_count {
+            break;
+        }
+    }
+
+    let limit = 200;
+    let mut domains = vec![];
+    for zone in &zones {
+        let mut offset = 0;
+        loop {
+            let domains_param = describe_acceleration_domains(
+                &credentials.get().await?,
+                zone.zone_id.clone(),
+                offset,
+                limit,
+                host.to_string(),
+            ).await?;
+            let domains_response = serde_json::from_str::<TencentCloudResponse<DescribeAccelerationDomainsData>>(&domains_param)?;
+            if let Some(error) = domains_response.response.error {
+                return Err(anyhow::anyhow!("查询站点 {} 的加速域名失败: {}", zone.zone_name, error));
+            }
+            let Some(data) = domains_response.response.data else {
+                break;
+            };
+            let count = data.acceleration_domains.len() as i64;
+            tracing::debug!("站点 {} 的加速域名: {:?}", zone.zone_name, data.acceleration_domains.iter().map(|domain| &domain.domain_name).collect::<Vec<_>>());
+            domains.extend(data.acceleration_domains);
+            offset += count;
+            if count == 0 || offset >= data.total_count {
+                break;
+            }
+        }
+    }
+    Ok(domains)
+}
+
+// 查询 EO 中被证书 SAN 覆盖的加速域名，并按 include/exclude 过滤
+pub async fn discover(credentials: &CredentialProvider, sans: &[String], args: &Args) -> anyhow::Result<Vec<String>> {
+    tracing::info!("正在查询 EO 加速域名...");
+    let domains: Vec<String> = acceleration_domains(credentials, &teo_host(args.intl.unwrap_or(false))).await?
+        .into_iter()
+        .map(|domain| domain.domain_name)
+        .collect();
+    let selected = select(
+        &domains,
+        sans,
+        args.include_domains.as_deref().unwrap_or_default(),
+        args.exclude_domains.as_deref().unwrap_or_default(),
+    );
+    tracing::info!("共 {} 个加速域名，证书覆盖并通过过滤的域名: {:?}", domains.len(), selected);
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.example.com", "www.example.com"));
+        assert!(glob_matches("*.example.com", "a.b.example.com"));
+        assert!(!glob_matches("*.example.com", "example.com"));
+        assert!(glob_matches("test-?.example.com", "TEST-1.example.com"));
+        assert!(!glob_matches("test-?.example.com", "test-12.example.com"));
+        assert!(glob_matches("*", "example.com"));
+        assert!(glob_matches("api*", "api.example.com"));
+        assert!(!glob_matches("api*", "www.api.example.com"));
+    }
+
+    #[test]
+    fn test_select() {
+        let domains: Vec<String> = ["example.com", "www.example.com", "api.example.com", "a.b.example.com", "other.com", "test-api.example.com"]
+            .iter().map(|domain| domain.to_string()).collect();
+        let sans = vec!["example.com".to_string(), "*.example.com".to_string()];
+
+        assert_eq!(select(&domains, &sans, &[], &[]), vec!["api.example.com", "example.com", "test-api.example.com", "www.example.com"]);
+        assert_eq!(select(&domains, &sans, &["*api*".to_string()], &[]), vec!["api.example.com", "test-api.example.com"]);
+        assert_eq!(select(&domains, &sans, &[], &["test-*".to_string(), "example.com".to_string()]), vec!["api.example.com", "www.example.com"]);
+    }
+}
@@ -0,0 +1,181 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+use super::notify::{Message, Notifier, SendFuture};
+use super::secret::Secret;
+
+// SMTP 邮件通知，可以发给多个收件人，并附带 JSON 格式的报告
+pub struct Email {
+    host: String,
+    port: Option<u16>,
+    security: String,
+    username: Option<String>,
+    password: Option<Secret<String>>,
+    from: String,
+    to: Vec<String>,
+    attach_report: bool,
+}
+
+impl Email {
+    // smtp_host、smtp_from 和 smtp_to 都配置了才发送
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        let to = config.smtp_to.clone().filter(|to| !to.is_empty())?;
+        Some(Self {
+            host: config.smtp_host.clone()?,
+            port: config.smtp_port,
+            security: config.smtp_security.clone().unwrap_or("starttls".to_string()).to_lowercase(),
+            username: config.smtp_username.clone(),
+            password: config.smtp_password.clone(),
+            from: config.smtp_from.clone()?,
+            to,
+            attach_report: config.smtp_attach_report.unwrap_or(false),
+        })
+    }
+
+    fn transport(&self) -> anyhow::Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let mut builder = match self.security.as_str() {
+            // 465 端口，连接建立时就使用 TLS
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?,
+            // 587 端口，连接后通过 STARTTLS 升级，服务器不支持时报错
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?,
+            // 25 端口，明文传输，只应该用于内网的邮件中继
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host),
+            other => return Err(anyhow::anyhow!("不支持的 SMTP 加密方式 {}，可选 starttls、tls、none", other)),
+        };
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &self.username {
+            let password = self.password.as_ref().map(|password| password.expose().clone()).unwrap_or_default();
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+        Ok(builder.build())
+    }
+
+    fn email(&self, message: &Message) -> anyhow::Result<lettre::Message> {
+        let mut builder = lettre::Message::builder()
+            .from(self.from.parse::<Mailbox>().map_err(|e| anyhow::anyhow!("发件人 {} 格式错误: {}", self.from, e))?)
+            .subject(subject(message));
+        for to in &self.to {
+            builder = builder.to(to.parse::<Mailbox>().map_err(|e| anyhow::anyhow!("收件人 {} 格式错误: {}", to, e))?);
+        }
+        let text = SinglePart::plain(format(message));
+        if !self.attach_report {
+            return Ok(builder.singlepart(text)?);
+        }
+        let report = Attachment::new("report.json".to_string())
+            .body(serde_json::to_string_pretty(&message.event)?, ContentType::parse("application/json")?);
+        Ok(builder.multipart(MultiPart::mixed().singlepart(text).singlepart(report))?)
+    }
+}
+
+pub fn subject(message: &Message) -> String {
+    if message.event.job.is_empty() {
+        return format!("[tc-eo-ssl] {}{}", message.icon(), message.title);
+    }
+    format!("[tc-eo-ssl] {}{} - {}", message.icon(), message.title, message.event.job)
+}
+
+// 邮件正文使用纯文本，标题之后空一行
+pub fn format(message: &Message) -> String {
+    format!("{}{}\n\n{}\n", message.icon(), message.title, message.body)
+}
+
+impl Notifier for Email {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let email = self.email(message)?;
+            self.transport()?.send(email).await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    use super::*;
+    use crate::service::notify::Event;
+
+    // 本地 SMTP 替身服务，接收一封邮件，把收到的命令和邮件内容交给测试
+    async fn sink() -> (u16, tokio::sync::oneshot::Receiver<(Vec<String>, String)>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut commands = vec![];
+            let mut data = String::new();
+            writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let command = line.to_uppercase();
+                commands.push(line.clone());
+                let reply: &[u8] = if command.starts_with("EHLO") {
+                    b"250-localhost\r\n250 AUTH PLAIN LOGIN\r\n"
+                } else if command.starts_with("AUTH") {
+                    b"235 2.7.0 Authentication successful\r\n"
+                } else if command.starts_with("DATA") {
+                    writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+                    while let Some(line) = lines.next_line().await.unwrap() {
+                        if line == "." {
+                            break;
+                        }
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    b"250 OK\r\n"
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 OK\r\n"
+                };
+                writer.write_all(reply).await.unwrap();
+            }
+            sender.send((commands, data)).unwrap();
+        });
+        (port, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let (port, receiver) = sink().await;
+        let email = Email {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            security: "none".to_string(),
+            username: Some("user".to_string()),
+            password: Some(Secret::new("password".to_string())),
+            from: "tc-eo-ssl <noreply@example.com>".to_string(),
+            to: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            attach_report: true,
+        };
+        let event = Event { job: "example.com".to_string(), ..Default::default() }.failure(&anyhow::anyhow!("InvalidParameter"));
+        email.send(&Message::new("证书部署失败\n错误信息: {{error}}", &event)).await.unwrap();
+
+        let (commands, data) = receiver.await.unwrap();
+        assert!(commands.iter().any(|command| command.starts_with("AUTH")));
+        assert!(commands.contains(&"MAIL FROM:<noreply@example.com>".to_string()));
+        assert!(commands.contains(&"RCPT TO:<a@example.com>".to_string()));
+        assert!(commands.contains(&"RCPT TO:<b@example.com>".to_string()));
+        assert!(data.contains("Content-Type: multipart/mixed"));
+        assert!(data.contains("filename=\"report.json\""));
+    }
+
+    #[test]
+    fn test_format() {
+        let event = Event { job: "example.com".to_string(), ..Default::default() }.success();
+        let message = Message::new("证书部署成功\n域名: www.example.com", &event);
+        assert_eq!(subject(&message), "[tc-eo-ssl] ✅证书部署成功 - example.com");
+        assert_eq!(format(&message), "✅证书部署成功\n\n域名: www.example.com\n");
+    }
+}
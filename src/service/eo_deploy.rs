@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use super::credential::CredentialProvider;
+use super::tc_request::TencentCloudResponse;
+use super::teo_api::*;
+
+// 查询域名所属的站点，返回 站点ID -> 该站点下要部署的加速域名
+pub async fn resolve_zones(
+    credentials: &CredentialProvider,
+    instance_id_list: &[String],
+    host: &str,
+) -> anyhow::Result<HashMap<String, Vec<AccelerationDomain>>> {
+    let mut domains: HashMap<String, AccelerationDomain> = super::discover::acceleration_domains(credentials, host).await?
+        .into_iter()
+        .map(|domain| (domain.domain_name.to_lowercase(), domain))
+        .collect();
+    let mut zones: HashMap<String, Vec<AccelerationDomain>> = HashMap::new();
+    let mut missing = vec![];
+    for instance_id in instance_id_list {
+        match domains.remove(&instance_id.to_lowercase()) {
+            Some(domain) => zones.entry(domain.zone_id.clone()).or_default().push(domain),
+            None => missing.push(instance_id.clone()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!("以下域名不是 EO 的加速域名: {:?}", missing));
+    }
+    Ok(zones)
+}
+
+// 域名当前配置的 SSL 托管证书，用于回滚，同时配置了 RSA 和 ECC 证书时都记录，使用免费证书或未配置证书的域名不记录
+pub fn previous_certificate_ids(zones: &HashMap<String, Vec<AccelerationDomain>>) -> HashMap<String, Vec<String>> {
+    let mut previous = HashMap::new();
+    for domain in zones.values().flatten() {
+        let Some(certificate) = &domain.certificate else {
+            continue;
+        };
+        if certificate.mode.as_deref() != Some("sslcert") {
+            continue;
+        }
+        let certificate_ids: Vec<String> = certificate.list.iter().flatten().map(|info| info.cert_id.clone()).collect();
+        if !certificate_ids.is_empty() {
+            previous.insert(domain.domain_name.clone(), certificate_ids);
+        }
+    }
+    previous
+}
+
+async fn modify(
+    credentials: &CredentialProvider,
+    zone_id: &str,
+    hosts: &[String],
+    mode: &str,
+    certificate_ids: &[String],
+    host: &str,
+) -> anyhow::Result<()> {
+    let modify_param = modify_hosts_certificate(
+        &credentials.get().await?,
+        zone_id.to_string(),
+        hosts.to_vec(),
+        mode,
+        certificate_ids,
+        host.to_string(),
+    ).await?;
+    let modify_response = serde_json::from_str::<TencentCloudResponse<ModifyHostsCertificateData>>(&modify_param)?;
+    if let Some(error) = modify_response.response.error {
+        return Err(error.with_request_id(&modify_response.response.request_id).into());
+    }
+    Ok(())
+}
+
+// 按站点调用 ModifyHostsCertificate 配置证书，结果是同步的，
+// 某个站点失败且开启了免费证书兜底时，把该站点的域名切换为 EO 免费证书，但仍然算作失败
+pub async fn deploy(
+    credentials: &CredentialProvider,
+    zones: &HashMap<String, Vec<AccelerationDomain>>,
+    certificate_ids: &[String],
+    free_cert_fallback: bool,
+    host: &str,
+) -> anyhow::Result<()> {
+    let mut errors = vec![];
+    for (zone_id, domains) in zones {
+        let hosts: Vec<String> = domains.iter().map(|domain| domain.domain_name.clone()).collect();
+        tracing::info!("正在为站点 {} 的域名 {:?} 配置证书 {:?}...", zone_id, hosts, certificate_ids);
+        let Err(e) = modify(credentials, zone_id, &hosts, "sslcert", certificate_ids, host).await else {
+            tracing::info!("站点 {} 的域名 {:?} 证书配置成功", zone_id, hosts);
+            continue;
+        };
+        tracing::error!("站点 {} 的域名 {:?} 证书配置失败: {}", zone_id, hosts, e);
+        if !free_cert_fallback {
+            errors.push(format!("{:?}: {}", hosts, e));
+            continue;
+        }
+        tracing::info!("正在把域名 {:?} 切换为 EO 免费证书...", hosts);
+        match modify(credentials, zone_id, &hosts, "eofreecert", &[], host).await {
+            Ok(_) => errors.push(format!("{:?}: {}，已切换为 EO 免费证书", hosts, e)),
+            Err(fallback_error) => errors.push(format!("{:?}: {}，切换为 EO 免费证书也失败了: {}", hosts, e, fallback_error)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("部署未全部成功: {}", errors.join("; ")));
+    }
+    Ok(())
+}
+
+// 把域名恢复为部署前配置的证书，按站点和证书列表分组调用 ModifyHostsCertificate，
+// 同时配置了 RSA 和 ECC 证书的域名会一起恢复
+pub async fn rollback(
+    credentials: &CredentialProvider,
+    previous: &HashMap<String, Vec<String>>,
+    host: &str,
+) -> anyhow::Result<()> {
+    let domains: Vec<String> = previous.keys().cloned().collect();
+    let zones = resolve_zones(credentials, &domains, host).await?;
+    let mut errors = vec![];
+    for (zone_id, domains) in &zones {
+        let mut groups: HashMap<&Vec<String>, Vec<String>> = HashMap::new();
+        for domain in domains {
+            if let Some(certificate_ids) = previous.get(&domain.domain_name) {
+                groups.entry(certificate_ids).or_default().push(domain.domain_name.clone());
+            }
+        }
+        for (certificate_ids, hosts) in groups {
+            tracing::info!("正在回滚站点 {} 的域名 {:?} 到证书 {:?}...", zone_id, hosts, certificate_ids);
+            if let Err(e) = modify(credentials, zone_id, &hosts, "sslcert", certificate_ids, host).await {
+                errors.push(format!("{:?} -> {:?}: {}", hosts, certificate_ids, e));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("回滚失败: {}", errors.join("; ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_previous_certificate_ids() {
+        let domains = serde_json::from_str::<Vec<AccelerationDomain>>(r#"[
+            {"ZoneId": "zone-1", "DomainName": "a.example.com", "Certificate": {"Mode": "sslcert", "List": [{"CertId": "rsa"}, {"CertId": "ecc"}]}},
+            {"ZoneId": "zone-1", "DomainName": "b.example.com", "Certificate": {"Mode": "eofreecert", "List": [{"CertId": "teo-free"}]}},
+            {"ZoneId": "zone-1", "DomainName": "c.example.com", "Certificate": {"Mode": "disable"}},
+            {"ZoneId": "zone-1", "DomainName": "d.example.com"}
+        ]"#).unwrap();
+        let zones = HashMap::from([("zone-1".to_string(), domains)]);
+
+        let previous = previous_certificate_ids(&zones);
+        assert_eq!(previous, HashMap::from([("a.example.com".to_string(), vec!["rsa".to_string(), "ecc".to_string()])]));
+    }
+}
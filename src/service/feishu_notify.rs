@@ -0,0 +1,83 @@
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+
+use super::notify::{Message, Notifier, SendFuture};
+use super::secret::Secret;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 飞书/Lark 自定义机器人，配置了签名校验密钥时在请求体中附加 timestamp 和 sign
+pub struct Feishu {
+    webhook_url: Secret<String>,
+    secret: Option<Secret<String>>,
+}
+
+impl Feishu {
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        Some(Self {
+            webhook_url: config.feishu_webhook_url.clone()?,
+            secret: config.feishu_secret.clone(),
+        })
+    }
+}
+
+// 签名：以 "秒级时间戳\n密钥" 为 key 对空字符串做 HmacSHA256，再 base64
+pub fn sign(secret: &str, timestamp: i64) -> anyhow::Result<String> {
+    let mac = HmacSha256::new_from_slice(format!("{}\n{}", timestamp, secret).as_bytes())?;
+    Ok(openssl::base64::encode_block(&mac.finalize().into_bytes()))
+}
+
+// 飞书使用消息卡片，标题放在卡片头部并按状态着色，正文使用卡片的 markdown 组件
+pub fn card(message: &Message) -> serde_json::Value {
+    json!({
+        "header": {
+            "title": {"tag": "plain_text", "content": format!("{}{}", message.icon(), message.title)},
+            "template": if message.is_success() { "green" } else { "red" },
+        },
+        "elements": [{"tag": "markdown", "content": message.body}],
+    })
+}
+
+impl Notifier for Feishu {
+    fn name(&self) -> &'static str {
+        "feishu"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let mut body = json!({"msg_type": "interactive", "card": card(message)});
+            if let Some(secret) = &self.secret {
+                let timestamp = chrono::Utc::now().timestamp();
+                body["timestamp"] = json!(timestamp.to_string());
+                body["sign"] = json!(sign(secret.expose(), timestamp)?);
+            }
+            let response = super::notify::post_json(self.webhook_url.expose(), &body).await?;
+            // 成功时 code 为 0
+            if response["code"].as_i64() != Some(0) {
+                return Err(anyhow::anyhow!("{}", response));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::notify::Event;
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(sign("SECxxx", 1700000000).unwrap(), "AGlCYeuANPxjQxWi+45gUfGvpk6zsXOCQTD6EGVkrps=");
+    }
+
+    #[test]
+    fn test_card() {
+        let event = Event::default().failure(&anyhow::anyhow!("InvalidParameter"));
+        let card = card(&Message::new("证书部署失败\n域名: www.example.com\n错误信息: {{error}}", &event));
+        assert_eq!(card["header"]["template"], "red");
+        assert_eq!(card["header"]["title"]["content"], "❌证书部署失败");
+        assert_eq!(card["elements"][0]["content"], "域名: www.example.com\n错误信息: InvalidParameter");
+    }
+}
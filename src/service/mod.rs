@@ -1,2 +1,27 @@
+pub mod cert;
+pub mod check;
+pub mod cleanup;
+pub mod credential;
+pub mod daemon;
+pub mod dingtalk_notify;
+pub mod discover;
+pub mod email_notify;
+pub mod eo_deploy;
+pub mod feishu_notify;
+pub mod notify;
+pub mod plan;
+pub mod replace;
+pub mod resource;
+pub mod secret;
+pub mod ssl_api;
+pub mod state;
 pub mod tc_request;
-pub mod tg_notify;
\ No newline at end of file
+pub mod template;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod tg_bot;
+pub mod teo_api;
+pub mod tg_notify;
+pub mod watch;
+pub mod webhook_notify;
+pub mod wecom_notify;
\ No newline at end of file
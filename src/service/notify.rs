@@ -0,0 +1,234 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::Serialize;
+
+use crate::config::args_conf::Args;
+
+use super::tc_request::TencentCloudResponseError;
+
+// 通知事件，webhook 以 JSON 发送，其他渠道按模板渲染为消息
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Event {
+    // deploy、rollback 或 check
+    pub action: String,
+    pub job: String,
+    pub domains: Vec<String>,
+    pub certificate_ids: Vec<String>,
+    pub fingerprint: Option<String>,
+    pub subject: Option<String>,
+    pub sans: Vec<String>,
+    // 本地证书的到期时间，RFC 3339 格式
+    pub not_after: Option<String>,
+    pub days_remaining: Option<i64>,
+    pub deploy_record_id: Option<i64>,
+    // success 或 failure
+    pub status: String,
+    // 腾讯云接口返回的错误码和 RequestId，其他错误为空
+    pub error_code: Option<String>,
+    pub request_id: Option<String>,
+    pub error: Option<String>,
+    // 证书检查发现的问题
+    pub problems: Vec<String>,
+    // 从开始执行到发送通知的耗时，单位秒
+    pub duration: Option<u64>,
+    pub hostname: String,
+    pub timestamp: String,
+}
+
+impl Event {
+    pub fn success(mut self) -> Self {
+        self.status = "success".to_string();
+        self.stamp()
+    }
+
+    pub fn failure(mut self, error: &anyhow::Error) -> Self {
+        self.status = "failure".to_string();
+        if let Some(response_error) = response_error(error) {
+            self.error_code = Some(response_error.code.clone());
+            self.request_id = response_error.request_id.clone();
+        }
+        self.error = Some(error.to_string());
+        self.stamp()
+    }
+
+    // 证书检查发现问题，按失败通知
+    pub fn problems(mut self, problems: Vec<String>) -> Self {
+        self.status = "failure".to_string();
+        self.problems = problems;
+        self.stamp()
+    }
+
+    // 填入本地证书的主体、SAN 和到期时间
+    pub fn certificate(mut self, cert: &openssl::x509::X509) -> Self {
+        self.subject = Some(super::cert::subject(cert));
+        self.sans = super::cert::dns_names(cert);
+        if let Ok(not_after) = super::cert::not_after(cert) {
+            self.not_after = Some(not_after.to_rfc3339());
+            self.days_remaining = Some((not_after - chrono::Utc::now()).num_days());
+        }
+        self
+    }
+
+    fn stamp(mut self) -> Self {
+        self.hostname = hostname::get().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        self.timestamp = chrono::Local::now().to_rfc3339();
+        self
+    }
+}
+
+// 按模板渲染后发给某个通知渠道的消息，第一行为标题，其余为正文，各渠道按自己支持的 markdown 格式排版
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub title: String,
+    pub body: String,
+    pub event: Event,
+}
+
+impl Message {
+    pub fn new(template: &str, event: &Event) -> Self {
+        let rendered = super::template::render(template, &super::template::variables(event));
+        let (title, body) = rendered.split_once('\n').unwrap_or((&rendered, ""));
+        Self { title: title.to_string(), body: body.to_string(), event: event.clone() }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.event.status == "success"
+    }
+
+    // 标题前的状态图标
+    pub fn icon(&self) -> &'static str {
+        if self.is_success() { "✅" } else { "❌" }
+    }
+}
+
+// 错误链中腾讯云接口返回的错误
+pub fn response_error(error: &anyhow::Error) -> Option<&TencentCloudResponseError> {
+    error.chain().find_map(|cause| cause.downcast_ref::<TencentCloudResponseError>())
+}
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+
+// 通知渠道，配置了多个渠道时每个渠道都会发送
+pub trait Notifier: Send + Sync {
+    // 渠道名称，也是配置文件中自定义模板的名称
+    fn name(&self) -> &'static str;
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a>;
+}
+
+// 按参数创建所有已配置的通知渠道
+pub fn notifiers(args: &Args) -> Vec<Box<dyn Notifier>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = vec![];
+    if let Some(telegram) = super::tg_notify::Telegram::new(args) {
+        notifiers.push(Box::new(telegram));
+    }
+    if let Some(wecom) = super::wecom_notify::WeCom::new(args) {
+        notifiers.push(Box::new(wecom));
+    }
+    if let Some(dingtalk) = super::dingtalk_notify::DingTalk::new(args) {
+        notifiers.push(Box::new(dingtalk));
+    }
+    if let Some(feishu) = super::feishu_notify::Feishu::new(args) {
+        notifiers.push(Box::new(feishu));
+    }
+    if let Some(email) = super::email_notify::Email::new(args) {
+        notifiers.push(Box::new(email));
+    }
+    if let Some(webhook) = super::webhook_notify::Webhook::new(args) {
+        notifiers.push(Box::new(webhook));
+    }
+    notifiers
+}
+
+// 通过所有已配置的渠道发送通知，每个渠道按自己的模板渲染消息，某个渠道发送失败只记录日志，不影响其他渠道
+pub async fn send(args: &Args, event: &Event) {
+    let notifiers = notifiers(args);
+    if notifiers.is_empty() {
+        tracing::debug!("未配置通知渠道，不发送通知消息");
+        return;
+    }
+    let language = args.notify_language.as_deref().unwrap_or("zh");
+    let kind = super::template::kind(event);
+    for notifier in notifiers {
+        let Some(template) = super::template::find(args.notify_templates.as_ref(), notifier.name(), language, &kind) else {
+            tracing::warn!("没有找到 {} 通知模板 {}，不发送", notifier.name(), kind);
+            continue;
+        };
+        let message = Message::new(template, event);
+        tracing::info!("发送 {} 通知消息", notifier.name());
+        match notifier.send(&message).await {
+            Ok(_) => tracing::info!("{} 消息发送成功", notifier.name()),
+            Err(e) => tracing::error!("{} 消息发送失败: {}", notifier.name(), e),
+        }
+    }
+}
+
+// 以 JSON 格式 POST 到机器人 webhook，返回响应体，
+// 请求失败时去掉错误信息中的地址，避免地址中的 key 出现在日志里
+pub async fn post_json(url: &str, body: &serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        reqwest::header::HeaderValue::from_static("application/json"),
+    );
+    headers.insert(reqwest::header::USER_AGENT, reqwest::header::HeaderValue::from_static("tc-eo-ssl"));
+    let client = reqwest::Client::new();
+    let response = client
+        .post(url)
+        .headers(headers)
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|e| e.without_url())?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.without_url())?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("HTTP {}: {}", status, text));
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::secret::Secret;
+
+    #[test]
+    fn test_notifiers() {
+        assert!(notifiers(&Args::default()).is_empty());
+
+        let args = Args {
+            tg_bot_token: Some(Secret::new("token".to_string())),
+            wecom_webhook_url: Some(Secret::new("https://qyapi.weixin.qq.com/cgi-bin/webhook/send?key=key".to_string())),
+            feishu_webhook_url: Some(Secret::new("https://open.feishu.cn/open-apis/bot/v2/hook/hook".to_string())),
+            ..Default::default()
+        };
+        // TG 缺少 chat_id 时不发送
+        let names: Vec<&str> = notifiers(&args).iter().map(|notifier| notifier.name()).collect();
+        assert_eq!(names, vec!["wecom", "feishu"]);
+    }
+
+    #[test]
+    fn test_failure() {
+        let error = TencentCloudResponseError {
+            code: "FailedOperation.CertificateNotFound".to_string(),
+            message: "证书不存在".to_string(),
+            request_id: Some("request-id".to_string()),
+        };
+        let event = Event::default().failure(&anyhow::Error::new(error).context("部署失败"));
+        assert_eq!(event.status, "failure");
+        assert_eq!(event.error_code.as_deref(), Some("FailedOperation.CertificateNotFound"));
+        assert_eq!(event.request_id.as_deref(), Some("request-id"));
+        assert_eq!(Event::default().failure(&anyhow::anyhow!("私钥与证书不匹配")).error_code, None);
+    }
+
+    #[test]
+    fn test_message() {
+        let event = Event { action: "deploy".to_string(), job: "example.com".to_string(), ..Default::default() }.success();
+        let message = Message::new("{{job}} 部署成功\n主机: {{hostname}}\n错误: {{error}}", &event);
+        assert_eq!(message.title, "example.com 部署成功");
+        assert_eq!(message.body, format!("主机: {}", event.hostname));
+        assert!(message.is_success());
+    }
+}
@@ -0,0 +1,138 @@
+use serde::Serialize;
+
+use super::check::CheckedCertificate;
+
+// 一个域名的部署计划
+#[derive(Debug, Serialize)]
+pub struct DomainPlan {
+    pub domain: String,
+    // sslcert SSL 托管证书，eofreecert EO 免费证书，disable 未配置证书，不是 EO 加速域名时为空
+    pub mode: Option<String>,
+    // 当前绑定的证书
+    pub current: Vec<CheckedCertificate>,
+    // 本地证书的 SAN 是否覆盖该域名，同时部署 RSA 和 ECC 证书时要求都覆盖
+    pub covered: bool,
+    // 部署后绑定的证书是否会变化
+    pub change: bool,
+}
+
+impl DomainPlan {
+    pub fn new(domain: &str, mode: Option<&str>, current: Vec<CheckedCertificate>, local: &[CheckedCertificate]) -> Self {
+        let covered = !local.is_empty() && local.iter().all(|local| local.sans.iter().any(|san| super::cert::san_covers(san, domain)));
+        let change = mode.is_some() && super::check::mismatch_problem(domain, local, &current).is_some();
+        Self { domain: domain.to_string(), mode: mode.map(|mode| mode.to_string()), current, covered, change }
+    }
+}
+
+// 一个任务的部署计划，只根据查询结果生成，不会修改任何配置
+#[derive(Debug, Serialize)]
+pub struct JobPlan {
+    pub job: String,
+    // 本地证书，已上传过时 certificate_id 为已上传证书的ID
+    pub local: Vec<CheckedCertificate>,
+    // 是否有本地证书还没有上传
+    pub upload: bool,
+    pub domains: Vec<DomainPlan>,
+    pub changed_domains: Vec<String>,
+    pub uncovered_domains: Vec<String>,
+}
+
+impl JobPlan {
+    pub fn new(job: &str, local: Vec<CheckedCertificate>, domains: Vec<DomainPlan>) -> Self {
+        let select = |predicate: fn(&DomainPlan) -> bool| {
+            domains.iter().filter(|domain| predicate(domain)).map(|domain| domain.domain.clone()).collect::<Vec<String>>()
+        };
+        Self {
+            job: job.to_string(),
+            upload: local.iter().any(|local| local.certificate_id.is_none()),
+            changed_domains: select(|domain| domain.change),
+            uncovered_domains: select(|domain| !domain.covered),
+            local,
+            domains,
+        }
+    }
+
+    // 以制表符分割的表格输出
+    pub fn table(&self) -> String {
+        let format_time = |certificate: &CheckedCertificate| {
+            certificate.not_after.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string()
+        };
+        let yes_no = |value: bool| if value { "是" } else { "否" };
+        let list = |items: &[String]| if items.is_empty() { "无".to_string() } else { items.join(", ") };
+        let mut lines = vec![format!("任务: {}", self.job), "本地证书\t指纹\tSAN\t到期时间\tCertificateId".to_string()];
+        for local in &self.local {
+            lines.push(format!(
+                "{}\t{}\t{}\t{}\t{}",
+                local.name,
+                local.fingerprint,
+                local.sans.join(","),
+                format_time(local),
+                local.certificate_id.as_deref().unwrap_or("未上传"),
+            ));
+        }
+        lines.push("域名\t当前证书\t颁发者\tSAN\t到期时间\t覆盖\t变化".to_string());
+        for domain in &self.domains {
+            let covered = yes_no(domain.covered);
+            let change = yes_no(domain.change);
+            if domain.current.is_empty() {
+                let mode = domain.mode.as_deref().unwrap_or("不是 EO 加速域名");
+                lines.push(format!("{}\t{}\t-\t-\t-\t{}\t{}", domain.domain, mode, covered, change));
+            }
+            for current in &domain.current {
+                lines.push(format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    domain.domain,
+                    current.certificate_id.as_deref().unwrap_or("-"),
+                    current.issuer,
+                    current.sans.join(","),
+                    format_time(current),
+                    covered,
+                    change,
+                ));
+            }
+        }
+        lines.push(format!("需要上传: {}", yes_no(self.upload)));
+        lines.push(format!("会变化的域名: {}", list(&self.changed_domains)));
+        lines.push(format!("证书未覆盖的域名: {}", list(&self.uncovered_domains)));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::test_util::{self_signed, to_pem};
+
+    fn checked(name: &str, certificate_id: Option<&str>, sans: &[&str]) -> CheckedCertificate {
+        let (cert, key) = self_signed(sans[0], sans, 1, 60);
+        let (pem, _) = to_pem(&[&cert], &key);
+        CheckedCertificate::from_pem(name, certificate_id, &pem).unwrap()
+    }
+
+    #[test]
+    fn test_plan() {
+        let local = checked("cert.pem", None, &["example.com", "*.example.com"]);
+        let old = checked("example.com", Some("old"), &["example.com", "*.example.com"]);
+        let same = CheckedCertificate { name: "www.example.com".to_string(), certificate_id: Some("new".to_string()), ..local.clone() };
+        let domains = vec![
+            DomainPlan::new("example.com", Some("sslcert"), vec![old], std::slice::from_ref(&local)),
+            DomainPlan::new("www.example.com", Some("sslcert"), vec![same], std::slice::from_ref(&local)),
+            DomainPlan::new("a.b.example.com", Some("eofreecert"), vec![], std::slice::from_ref(&local)),
+            DomainPlan::new("other.com", None, vec![], std::slice::from_ref(&local)),
+        ];
+        let plan = JobPlan::new("example", vec![local], domains);
+        assert!(plan.upload);
+        assert_eq!(plan.changed_domains, vec!["example.com", "a.b.example.com"]);
+        assert_eq!(plan.uncovered_domains, vec!["a.b.example.com", "other.com"]);
+
+        let table = plan.table();
+        assert!(table.contains("\ncert.pem\t"));
+        assert!(table.contains("\nwww.example.com\tnew\tCN=example.com\texample.com,*.example.com\t"));
+        assert!(table.contains("\nother.com\t不是 EO 加速域名\t-\t-\t-\t否\t否\n"));
+        assert!(table.ends_with("需要上传: 是\n会变化的域名: example.com, a.b.example.com\n证书未覆盖的域名: a.b.example.com, other.com"));
+
+        let value = serde_json::to_value(&plan).unwrap();
+        assert_eq!(value["domains"][0]["current"][0]["certificate_id"], "old");
+        assert!(value["local"][0]["not_after"].as_str().unwrap().ends_with('Z'));
+    }
+}
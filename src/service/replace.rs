@@ -0,0 +1,205 @@
+use std::time::{Duration, Instant};
+
+use super::credential::CredentialProvider;
+use super::ssl_api::*;
+use super::tc_request::TencentCloudResponse;
+
+// 与新证书 SAN 相同、加密算法相同的其他证书，按到期时间从晚到早排序
+pub fn same_domain_certificates<'a>(
+    certificates: &'a [CertificateItem],
+    certificate_id: &str,
+    sans: &[String],
+    encrypt_algorithm: &str,
+) -> Vec<&'a CertificateItem> {
+    let mut sans: Vec<String> = sans.iter().map(|san| san.to_lowercase()).collect();
+    sans.sort();
+    sans.dedup();
+    let mut matched: Vec<&CertificateItem> = certificates
+        .iter()
+        .filter(|certificate| certificate.certificate_id != certificate_id)
+        .filter(|certificate| {
+            certificate.encrypt_algorithm.as_deref().is_none_or(|algorithm| algorithm.eq_ignore_ascii_case(encrypt_algorithm))
+        })
+        .filter(|certificate| {
+            let mut names: Vec<String> = certificate.subject_alt_name.iter().flatten().map(|name| name.to_lowercase()).collect();
+            names.sort();
+            names.dedup();
+            names == sans
+        })
+        .collect();
+    matched.sort_by_key(|certificate| std::cmp::Reverse(certificate.end_time()));
+    matched
+}
+
+// 从证书列表中选出被新证书替换的旧证书：SAN 相同、加密算法相同、到期时间不晚于新证书的其他证书中到期时间最晚的，
+// 到期时间无法解析的证书不选，避免把更新的证书替换成旧证书
+pub fn select_old_certificate(
+    certificates: &[CertificateItem],
+    certificate_id: &str,
+    sans: &[String],
+    encrypt_algorithm: &str,
+    not_after: chrono::NaiveDateTime,
+) -> Option<String> {
+    same_domain_certificates(certificates, certificate_id, sans, encrypt_algorithm)
+        .into_iter()
+        .find(|certificate| certificate.end_time().is_some_and(|end_time| end_time <= not_after))
+        .map(|certificate| certificate.certificate_id.clone())
+}
+
+// 分页查询符合条件的全部证书
+pub async fn describe_all_certificates(
+    credentials: &CredentialProvider,
+    filter: &CertificateFilter,
+    host: &str,
+) -> anyhow::Result<Vec<CertificateItem>> {
+    all_pages(async |offset| {
+        let filter = CertificateFilter { offset, ..filter.clone() };
+        let describe_param = describe_certificates(&credentials.get().await?, &filter, host.to_string()).await?;
+        DescribeCertificatesData::page(&describe_param)
+    }).await
+}
+
+// 按域名查询与新证书覆盖相同域名的旧证书
+pub async fn find_old_certificate(
+    credentials: &CredentialProvider,
+    certificate_public_key: &str,
+    certificate_id: &str,
+    host: &str,
+) -> anyhow::Result<Option<String>> {
+    let leaf = super::cert::leaf(certificate_public_key)?;
+    let Some(domain) = super::cert::primary_domain(&leaf) else {
+        return Ok(None);
+    };
+    let filter = CertificateFilter {
+        search_key: Some(domain),
+        ..Default::default()
+    };
+    let certificates = describe_all_certificates(credentials, &filter, host).await?;
+    Ok(select_old_certificate(
+        &certificates,
+        certificate_id,
+        &super::cert::dns_names(&leaf),
+        super::cert::encrypt_algorithm(&leaf)?,
+        api_time(super::cert::not_after(&leaf)?),
+    ))
+}
+
+// 创建一键更新任务，任务创建是异步的，重复调用直到返回 DeployRecordId
+pub async fn update(
+    credentials: &CredentialProvider,
+    certificate_id: &str,
+    old_certificate_id: &str,
+    resource_types: &[String],
+    resource_types_regions: &[(String, String)],
+    host: &str,
+) -> anyhow::Result<i64> {
+    for _ in 0..30 {
+        let update_param = update_certificate_instance(
+            &credentials.get().await?,
+            certificate_id.to_string(),
+            old_certificate_id.to_string(),
+            resource_types,
+            resource_types_regions.to_vec(),
+            host.to_string(),
+        ).await?;
+        let update_response = serde_json::from_str::<TencentCloudResponse<UpdateCertificateInstanceData>>(&update_param)?;
+        if let Some(error) = update_response.response.error {
+            return Err(error.with_request_id(&update_response.response.request_id).into());
+        }
+        if let Some(deploy_record_id) = update_response.response.data.and_then(|data| data.deploy_record_id).filter(|id| *id > 0) {
+            return Ok(deploy_record_id);
+        }
+        tracing::debug!("一键更新任务创建中...");
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+    Err(anyhow::anyhow!("创建一键更新任务超时"))
+}
+
+// 轮询一键更新记录，直到所有资源更新结束或超时，输出每个资源的更新结果，全部成功才返回 Ok
+pub async fn wait(
+    credentials: &CredentialProvider,
+    deploy_record_id: i64,
+    timeout: Duration,
+    host: &str,
+) -> anyhow::Result<()> {
+    let interval = Duration::from_secs(10);
+    let deadline = Instant::now() + timeout;
+    let mut empty_polls = 0;
+    loop {
+        let detail_param = describe_host_update_record_detail(
+            &credentials.get().await?,
+            deploy_record_id.to_string(),
+            host.to_string(),
+        ).await?;
+        let detail_response = serde_json::from_str::<TencentCloudResponse<DescribeHostUpdateRecordDetailData>>(&detail_param)?;
+        if let Some(error) = detail_response.response.error {
+            return Err(error.with_request_id(&detail_response.response.request_id).into());
+        }
+        let data = detail_response.response.data;
+        let running = data.as_ref().and_then(|data| data.running_total_count).unwrap_or(0);
+        let details: Vec<UpdateRecordDetail> = data
+            .and_then(|data| data.deploy_record_list)
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|group| group.list.unwrap_or_default())
+            .collect();
+
+        if details.is_empty() && running == 0 {
+            // 更新记录刚创建时可能还没有详情，连续两次为空认为旧证书没有绑定需要更新的资源
+            empty_polls += 1;
+            if empty_polls >= 2 {
+                tracing::info!("旧证书没有绑定需要更新的资源");
+                return Ok(());
+            }
+        } else if running == 0 && details.iter().all(|detail| detail.is_finished()) {
+            let mut failed = vec![];
+            for detail in &details {
+                tracing::info!("更新结果 {}: {} {}", detail.resource(), detail.status_text(), detail.error_msg.as_deref().unwrap_or(""));
+                if detail.status != 1 {
+                    failed.push(format!("{} {} {}", detail.resource(), detail.status_text(), detail.error_msg.as_deref().unwrap_or("")));
+                }
+            }
+            if !failed.is_empty() {
+                return Err(anyhow::anyhow!("一键更新未全部成功: {}", failed.join("; ")));
+            }
+            return Ok(());
+        }
+
+        if Instant::now() + interval > deadline {
+            let pending: Vec<String> = details.iter().filter(|detail| !detail.is_finished()).map(|detail| format!("{} {}", detail.resource(), detail.status_text())).collect();
+            return Err(anyhow::anyhow!("等待一键更新完成超时，DeployRecordId: {}，未完成: {}", deploy_record_id, pending.join("; ")));
+        }
+        tracing::info!("一键更新进行中，{} 秒后再次查询...", interval.as_secs());
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_old_certificate() {
+        let certificates = serde_json::from_str::<Vec<CertificateItem>>(r#"[
+            {"CertificateId": "new", "SubjectAltName": ["example.com", "*.example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2027-01-01 00:00:00"},
+            {"CertificateId": "newer", "SubjectAltName": ["example.com", "*.example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2027-02-01 00:00:00"},
+            {"CertificateId": "old", "SubjectAltName": ["*.example.com", "example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-10-01 00:00:00"},
+            {"CertificateId": "older", "SubjectAltName": ["example.com", "*.example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-07-01 00:00:00"},
+            {"CertificateId": "ecc", "SubjectAltName": ["example.com", "*.example.com"], "EncryptAlgorithm": "ECC", "CertEndTime": "2026-12-01 00:00:00"},
+            {"CertificateId": "other", "SubjectAltName": ["example.com"], "EncryptAlgorithm": "RSA", "CertEndTime": "2026-12-01 00:00:00"}
+        ]"#).unwrap();
+        let sans = vec!["example.com".to_string(), "*.example.com".to_string()];
+
+        let not_after = chrono::NaiveDateTime::parse_from_str("2027-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        assert_eq!(select_old_certificate(&certificates, "new", &sans, "RSA", not_after), Some("old".to_string()));
+        assert_eq!(select_old_certificate(&certificates, "new", &sans, "ECC", not_after), Some("ecc".to_string()));
+        assert_eq!(select_old_certificate(&certificates, "new", &["www.example.com".to_string()], "RSA", not_after), None);
+        // 不能选到期时间比新证书晚的证书
+        assert_eq!(select_old_certificate(&certificates, "new", &sans, "ECC", not_after - chrono::Duration::days(90)), None);
+        assert_eq!(
+            select_old_certificate(&certificates, "new", &sans, "RSA", not_after - chrono::Duration::days(100)),
+            Some("older".to_string())
+        );
+    }
+}
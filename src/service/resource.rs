@@ -0,0 +1,166 @@
+use std::str::FromStr;
+
+// DeployCertificateInstance 支持部署的云资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceType {
+    Teo,
+    Cdn,
+    Clb,
+    Waf,
+    Live,
+    Ddos,
+    Apigateway,
+    Vod,
+    Tke,
+    Cos,
+}
+
+impl FromStr for ResourceType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "teo" => Ok(ResourceType::Teo),
+            "cdn" => Ok(ResourceType::Cdn),
+            "clb" => Ok(ResourceType::Clb),
+            "waf" => Ok(ResourceType::Waf),
+            "live" => Ok(ResourceType::Live),
+            "ddos" => Ok(ResourceType::Ddos),
+            "apigateway" => Ok(ResourceType::Apigateway),
+            "vod" => Ok(ResourceType::Vod),
+            "tke" => Ok(ResourceType::Tke),
+            "cos" => Ok(ResourceType::Cos),
+            _ => Err(anyhow::anyhow!("不支持的资源类型 {}，可选 teo、cdn、clb、waf、live、ddos、apigateway、vod、tke、cos", s)),
+        }
+    }
+}
+
+impl ResourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceType::Teo => "teo",
+            ResourceType::Cdn => "cdn",
+            ResourceType::Clb => "clb",
+            ResourceType::Waf => "waf",
+            ResourceType::Live => "live",
+            ResourceType::Ddos => "ddos",
+            ResourceType::Apigateway => "apigateway",
+            ResourceType::Vod => "vod",
+            ResourceType::Tke => "tke",
+            ResourceType::Cos => "cos",
+        }
+    }
+
+    // 上传证书时的 CertificateUse，没有对应值的资源类型不传
+    pub fn certificate_use(&self) -> Option<&'static str> {
+        match self {
+            ResourceType::Teo => Some("teo"),
+            ResourceType::Cdn => Some("CDN"),
+            ResourceType::Clb => Some("CLB"),
+            ResourceType::Waf => Some("WAF"),
+            ResourceType::Live => Some("LIVE"),
+            ResourceType::Ddos => Some("DDOS"),
+            _ => None,
+        }
+    }
+
+    // 地域级的资源部署时需要指定地域
+    pub fn requires_region(&self) -> bool {
+        matches!(self, ResourceType::Clb | ResourceType::Apigateway | ResourceType::Tke | ResourceType::Cos)
+    }
+
+    // 实例ID是否就是域名，部署记录按域名记录，可以用于等待部署完成和回滚
+    pub fn is_domain(&self) -> bool {
+        matches!(self, ResourceType::Teo | ResourceType::Cdn | ResourceType::Waf | ResourceType::Live | ResourceType::Vod)
+    }
+
+    // 实例ID的格式说明
+    pub fn instance_id_format(&self) -> &'static str {
+        match self {
+            ResourceType::Teo | ResourceType::Cdn | ResourceType::Waf | ResourceType::Live | ResourceType::Vod => "域名，如 www.example.com",
+            ResourceType::Clb => "负载均衡ID|监听器ID，七层监听器需要加上域名，如 lb-xxxxxxxx|lbl-xxxxxxxx|www.example.com",
+            ResourceType::Ddos => "实例ID|域名|协议|端口，如 bgpip-xxxxxxxx|www.example.com|https|443",
+            ResourceType::Apigateway => "服务ID|域名，如 service-xxxxxxxx|api.example.com",
+            ResourceType::Tke => "集群ID|命名空间|Secret 名称，如 cls-xxxxxxxx|default|example-com-tls",
+            ResourceType::Cos => "地域|存储桶|域名，如 ap-guangzhou|bucket-1250000000|static.example.com",
+        }
+    }
+
+    // 实例ID中的域名，用于校验证书是否覆盖，七层以外的 CLB 监听器和 TKE 的实例ID中没有域名
+    pub fn domain<'a>(&self, instance_id: &'a str) -> Option<&'a str> {
+        let parts: Vec<&str> = instance_id.split('|').collect();
+        match self {
+            ResourceType::Teo | ResourceType::Cdn | ResourceType::Waf | ResourceType::Live | ResourceType::Vod => Some(instance_id),
+            ResourceType::Clb => parts.get(2).copied(),
+            ResourceType::Ddos | ResourceType::Apigateway => parts.get(1).copied(),
+            ResourceType::Tke => None,
+            ResourceType::Cos => parts.get(2).copied(),
+        }
+    }
+
+    // 校验实例ID的格式
+    pub fn check_instance_id(&self, instance_id: &str) -> anyhow::Result<()> {
+        if self.is_valid_instance_id(instance_id) {
+            return Ok(());
+        }
+        Err(anyhow::anyhow!("{} 资源的实例ID {} 格式错误，格式应为 {}", self.as_str(), instance_id, self.instance_id_format()))
+    }
+
+    fn is_valid_instance_id(&self, instance_id: &str) -> bool {
+        let parts: Vec<&str> = instance_id.split('|').collect();
+        match (self, parts.as_slice()) {
+            (ResourceType::Teo | ResourceType::Cdn | ResourceType::Waf | ResourceType::Live | ResourceType::Vod, [domain]) => is_domain(domain),
+            (ResourceType::Clb, [lb, listener]) => lb.starts_with("lb-") && listener.starts_with("lbl-"),
+            (ResourceType::Clb, [lb, listener, domain]) => lb.starts_with("lb-") && listener.starts_with("lbl-") && is_domain(domain),
+            (ResourceType::Ddos, [instance, domain, protocol, port]) => {
+                !instance.is_empty() && is_domain(domain) && !protocol.is_empty() && port.parse::<u16>().is_ok()
+            },
+            (ResourceType::Apigateway, [service, domain]) => service.starts_with("service-") && is_domain(domain),
+            (ResourceType::Tke, [cluster, namespace, secret]) => cluster.starts_with("cls-") && !namespace.is_empty() && !secret.is_empty(),
+            (ResourceType::Cos, [region, bucket, domain]) => !region.is_empty() && bucket.contains('-') && is_domain(domain),
+            _ => false,
+        }
+    }
+}
+
+// 是否是合法的域名，允许开头的通配符
+fn is_domain(domain: &str) -> bool {
+    let domain = domain.strip_prefix("*.").unwrap_or(domain);
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_instance_id() {
+        assert!(ResourceType::Teo.check_instance_id("*.example.com").is_ok());
+        assert!(ResourceType::Cdn.check_instance_id("lb-1|lbl-1").is_err());
+        assert!(ResourceType::Clb.check_instance_id("lb-12345678|lbl-12345678").is_ok());
+        assert!(ResourceType::Clb.check_instance_id("lb-12345678|lbl-12345678|www.example.com").is_ok());
+        assert!(ResourceType::Clb.check_instance_id("www.example.com").is_err());
+        assert!(ResourceType::Ddos.check_instance_id("bgpip-12345678|www.example.com|https|443").is_ok());
+        assert!(ResourceType::Ddos.check_instance_id("bgpip-12345678|www.example.com|https|https").is_err());
+        assert!(ResourceType::Apigateway.check_instance_id("service-12345678|api.example.com").is_ok());
+        assert!(ResourceType::Tke.check_instance_id("cls-12345678|default|example-com-tls").is_ok());
+        assert!(ResourceType::Cos.check_instance_id("ap-guangzhou|bucket-1250000000|static.example.com").is_ok());
+        assert!(ResourceType::Cos.check_instance_id("bucket-1250000000|static.example.com").is_err());
+    }
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(ResourceType::Clb.domain("lb-1|lbl-1"), None);
+        assert_eq!(ResourceType::Clb.domain("lb-1|lbl-1|www.example.com"), Some("www.example.com"));
+        assert_eq!(ResourceType::Cos.domain("ap-guangzhou|bucket-1250000000|static.example.com"), Some("static.example.com"));
+        assert_eq!(ResourceType::Tke.domain("cls-1|default|tls"), None);
+    }
+}
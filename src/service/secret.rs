@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use zeroize::Zeroize;
+
+// 敏感值的包装类型，Debug 和 Display 都只输出 ******，释放时清零内存
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    // 取出真实值，只在真正需要时使用，不要用于日志
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "******")
+    }
+}
+
+impl<T: Zeroize> std::fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "******")
+    }
+}
+
+impl std::str::FromStr for Secret<String> {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl From<String> for Secret<String> {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted() {
+        let secret = Secret::new("AKIDxxxxxxxx".to_string());
+        assert_eq!(format!("{}", secret), "******");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some(******)");
+        assert_eq!(secret.expose(), "AKIDxxxxxxxx");
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+// 每个任务保留的部署历史条数
+pub const HISTORY_LIMIT: usize = 10;
+
+// 持久化的运行状态，记录每个任务最后一次成功部署的证书，以及最后一次部署前各域名绑定的证书
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub jobs: HashMap<String, JobState>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobState {
+    pub fingerprint: String,
+    pub certificate_id: String,
+    // 同时部署 RSA 和 ECC 证书时的 ECC 证书
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecc_certificate_id: Option<String>,
+    // teo 部署方式是同步的，没有部署记录
+    pub deploy_record_id: Option<i64>,
+    pub deployed_at: String,
+    // 域名 -> 最后一次部署前绑定的 CertificateId，同时绑定了 RSA 和 ECC 证书时有两个，用于回滚
+    #[serde(default, deserialize_with = "previous_certificate_ids")]
+    pub previous_certificate_ids: HashMap<String, Vec<String>>,
+    // 最近的部署记录，最新的在前面，包括失败的部署
+    #[serde(default)]
+    pub history: Vec<History>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    // success 或 failure
+    pub status: String,
+    #[serde(default)]
+    pub certificate_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy_record_id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub deployed_at: String,
+}
+
+impl JobState {
+    // 记录一次部署，超过 HISTORY_LIMIT 条时丢弃最早的
+    pub fn push_history(&mut self, history: History) {
+        self.history.insert(0, history);
+        self.history.truncate(HISTORY_LIMIT);
+    }
+
+    // 记录部署前各域名绑定的证书，只记录与本次部署的证书不同的 CertificateId，
+    // 没有不同证书的域名保留原来的记录，重复部署同一个证书后仍然可以回滚到真正的上一个证书
+    pub fn record_previous(&mut self, previous: &HashMap<String, Vec<String>>, certificate_ids: &[String]) {
+        for (domain, bound) in previous {
+            let bound: Vec<String> = bound.iter().filter(|id| !certificate_ids.contains(id)).cloned().collect();
+            if !bound.is_empty() {
+                self.previous_certificate_ids.insert(domain.clone(), bound);
+            }
+        }
+    }
+}
+
+// 旧版本的状态文件每个域名只记录一个 CertificateId，读取时兼容
+fn previous_certificate_ids<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CertificateIds {
+        One(String),
+        Many(Vec<String>),
+    }
+    let previous = HashMap::<String, CertificateIds>::deserialize(deserializer)?;
+    Ok(previous
+        .into_iter()
+        .map(|(domain, certificate_ids)| match certificate_ids {
+            CertificateIds::One(certificate_id) => (domain, vec![certificate_id]),
+            CertificateIds::Many(certificate_ids) => (domain, certificate_ids),
+        })
+        .collect())
+}
+
+// 读取状态文件，文件不存在时返回空状态
+pub fn load(path: &str) -> anyhow::Result<State> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str::<State>(&content)
+            .map_err(|e| anyhow::anyhow!("解析状态文件 {} 失败: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(State::default()),
+        Err(e) => Err(anyhow::anyhow!("读取状态文件 {} 失败: {}", path, e)),
+    }
+}
+
+// 先写临时文件再重命名，避免写入一半时被中断导致状态文件损坏
+pub fn save(path: &str, state: &State) -> anyhow::Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(state)?)
+        .map_err(|e| anyhow::anyhow!("写入状态文件 {} 失败: {}", tmp_path, e))?;
+    std::fs::rename(&tmp_path, path)
+        .map_err(|e| anyhow::anyhow!("写入状态文件 {} 失败: {}", path, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_previous() {
+        let mut job_state = JobState::default();
+        let new = vec!["new".to_string()];
+        job_state.record_previous(&HashMap::from([("example.com".to_string(), vec!["old".to_string()])]), &new);
+        // 第二次部署同一个证书时域名已经绑定了新证书，回滚目标仍然是 old
+        job_state.record_previous(&HashMap::from([("example.com".to_string(), new.clone())]), &new);
+        assert_eq!(job_state.previous_certificate_ids, HashMap::from([("example.com".to_string(), vec!["old".to_string()])]));
+    }
+
+    #[test]
+    fn test_previous_certificate_ids() {
+        let state = serde_json::from_str::<State>(r#"{"jobs": {"example.com": {
+            "fingerprint": "", "certificate_id": "new", "deploy_record_id": null, "deployed_at": "",
+            "previous_certificate_ids": {"a.example.com": "old", "b.example.com": ["rsa", "ecc"]}
+        }}}"#).unwrap();
+        let previous = &state.jobs["example.com"].previous_certificate_ids;
+        assert_eq!(previous["a.example.com"], vec!["old"]);
+        assert_eq!(previous["b.example.com"], vec!["rsa", "ecc"]);
+    }
+}
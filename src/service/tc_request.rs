@@ -3,33 +3,37 @@ use hmac::{Hmac, Mac};
 use reqwest::header;
 use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::secret::Secret;
 
 type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug)]
 pub struct TencentCloudRequest {
-    secret_id: String,
-    secret_key: String,
+    secret_id: Secret<String>,
+    secret_key: Secret<String>,
     service: String,
     host: String,
     region: String,
     action: String,
     version: String,
-    payload: String,
-    token: String,
+    payload: Zeroizing<String>,
+    token: Secret<String>,
 }
 
 impl TencentCloudRequest {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        secret_id: String,
-        secret_key: String,
+        secret_id: Secret<String>,
+        secret_key: Secret<String>,
         service: String,
         host: String,
         region: String,
         action: String,
         version: String,
         payload: String,
-        token: String,
+        token: Secret<String>,
     ) -> Self {
         Self {
             secret_id,
@@ -39,36 +43,47 @@ impl TencentCloudRequest {
             region,
             action,
             version,
-            payload,
+            payload: Zeroizing::new(payload),
             token,
         }
     }
 
-    pub async fn send(&self) -> anyhow::Result<String> {
+    pub async fn send(&self) -> anyhow::Result<Zeroizing<String>> {
         let timestamp = Utc::now().timestamp();
         let date = Utc::now().format("%Y-%m-%d").to_string();
 
         // Step 1: Create canonical request
         let canonical_request = self.create_canonical_request()?;
-        println!("Canonical request:\n{}", canonical_request);
+        tracing::trace!("Canonical request:\n{}", canonical_request);
 
         // Step 2: Create string to sign
         let string_to_sign = self.create_string_to_sign(&canonical_request, &date, timestamp)?;
-        println!("String to sign:\n{}", string_to_sign);
+        tracing::trace!("String to sign:\n{}", string_to_sign);
 
         // Step 3: Calculate signature
         let signature = self.calculate_signature(&string_to_sign, &date)?;
-        println!("Signature: {}", signature);
+        tracing::trace!("Signature: {}", signature);
 
         // Step 4: Create authorization header
-        let authorization = self.create_authorization(&signature, &date)?;
-        println!("Authorization: {}", authorization);
+        let authorization = Zeroizing::new(self.create_authorization(&signature, &date)?);
+        tracing::trace!(
+            "Authorization: TC3-HMAC-SHA256 Credential={}/{}/{}/tc3_request, SignedHeaders=content-type;host;x-tc-action, Signature={}",
+            self.secret_id, date, self.service, signature
+        );
 
         // Step 5: Send request
+        tracing::debug!("调用腾讯云接口 {} {}", self.host, self.action);
         let response = self.send_request(&authorization, timestamp).await?;
         Ok(response)
     }
 
+    // 不需要签名的接口，如 STS AssumeRoleWithWebIdentity，Authorization 固定为 SKIP
+    pub async fn send_unsigned(&self) -> anyhow::Result<Zeroizing<String>> {
+        let timestamp = Utc::now().timestamp();
+        let response = self.send_request("SKIP", timestamp).await?;
+        Ok(response)
+    }
+
     fn create_canonical_request(&self) -> anyhow::Result<String> {
         let http_request_method = "POST";
         let canonical_uri = "/";
@@ -124,7 +139,7 @@ impl TencentCloudRequest {
         date: &str,
     ) -> anyhow::Result<String> {
         // Create secret date
-        let mut mac = HmacSha256::new_from_slice(format!("TC3{}", self.secret_key).as_bytes())?;
+        let mut mac = HmacSha256::new_from_slice(Zeroizing::new(format!("TC3{}", self.secret_key.expose())).as_bytes())?;
         mac.update(date.as_bytes());
         let secret_date = hex::encode(mac.finalize().into_bytes());
 
@@ -157,7 +172,7 @@ impl TencentCloudRequest {
 
         let authorization = format!(
             "{} Credential={}/{}, SignedHeaders={}, Signature={}",
-            algorithm, self.secret_id, credential_scope, signed_headers, signature
+            algorithm, self.secret_id.expose(), credential_scope, signed_headers, signature
         );
 
         Ok(authorization)
@@ -167,7 +182,7 @@ impl TencentCloudRequest {
         &self,
         authorization: &str,
         timestamp: i64,
-    ) -> anyhow::Result<String> {
+    ) -> anyhow::Result<Zeroizing<String>> {
         let client = reqwest::Client::new();
         let url = format!("https://{}", self.host);
 
@@ -200,22 +215,28 @@ impl TencentCloudRequest {
             header::HeaderName::from_static("x-tc-region"),
             header::HeaderValue::from_str(&self.region)?,
         );
-        if !self.token.is_empty() {
+        if !self.token.expose().is_empty() {
             headers.insert(
                 header::HeaderName::from_static("x-tc-token"),
-                header::HeaderValue::from_str(&self.token)?,
+                header::HeaderValue::from_str(self.token.expose())?,
             );
         }
 
+        // 请求体可能包含证书私钥，用 Zeroizing 作为 Bytes 的所有者，请求发送完成后 reqwest 释放请求体时清零，
+        // hyper 和 TLS 发送时写入的缓冲区不在这里的控制范围内
+        let body = bytes::Bytes::from_owner(Zeroizing::new(self.payload.as_bytes().to_vec()));
         let response = client
             .post(&url)
             .headers(headers)
-            .body(self.payload.clone())
+            .body(body)
             .send()
             .await?;
 
-        let response_text = response.text().await?;
-        Ok(response_text)
+        // 响应可能包含证书私钥或临时密钥，直接转换为可以清零的 String，不再复制，调用方释放时清零，
+        // 与请求体一样，hyper 接收时的缓冲区不在这里的控制范围内
+        let response_body = Vec::from(response.bytes().await?);
+        let response_text = String::from_utf8(response_body).map_err(|e| anyhow::anyhow!("响应不是有效的 UTF-8: {}", e.utf8_error()))?;
+        Ok(Zeroizing::new(response_text))
     }
 }
 
@@ -241,10 +262,23 @@ pub struct TencentCloudResponseError {
     pub code: String,
     #[serde(rename = "Message")]
     pub message: String,
+    // RequestId 在 Error 外层，返回错误时用 with_request_id 填入
+    #[serde(skip)]
+    pub request_id: Option<String>,
 }
 
 impl TencentCloudResponseError {
-    pub fn to_string(&self) -> String {
-        format!("{}: {}", self.code, self.message)
+    pub fn with_request_id(mut self, request_id: &str) -> Self {
+        self.request_id = Some(request_id.to_string());
+        self
     }
 }
+
+impl std::fmt::Display for TencentCloudResponseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+// 作为错误返回时保留错误码，通知时可以通过 downcast 取出
+impl std::error::Error for TencentCloudResponseError {}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use super::notify::Event;
+
+// 默认的中文通知模板，第一行为标题，其余为正文
+const ZH_DEPLOY_SUCCESS: &str = "证书部署成功
+任务: {{job}}
+域名: {{domains}}
+证书ID: {{certificate_id}}
+部署记录ID: {{deploy_record_id}}
+证书主体: {{subject}}
+SAN: {{sans}}
+到期时间: {{not_after}}，剩余 {{days_remaining}} 天
+耗时: {{duration}} 秒
+主机: {{hostname}}";
+
+const ZH_DEPLOY_FAILURE: &str = "证书部署失败
+任务: {{job}}
+域名: {{domains}}
+证书ID: {{certificate_id}}
+错误码: {{error_code}}
+错误信息: {{error}}
+RequestId: {{request_id}}
+耗时: {{duration}} 秒
+主机: {{hostname}}";
+
+const ZH_ROLLBACK_SUCCESS: &str = "证书回滚成功
+任务: {{job}}
+域名: {{domains}}
+回滚到证书ID: {{certificate_id}}
+耗时: {{duration}} 秒
+主机: {{hostname}}";
+
+const ZH_ROLLBACK_FAILURE: &str = "证书回滚失败
+任务: {{job}}
+域名: {{domains}}
+错误码: {{error_code}}
+错误信息: {{error}}
+RequestId: {{request_id}}
+耗时: {{duration}} 秒
+主机: {{hostname}}";
+
+const ZH_CHECK_FAILURE: &str = "证书检查发现问题
+任务: {{job}}
+域名: {{domains}}
+本地证书到期时间: {{not_after}}，剩余 {{days_remaining}} 天
+{{problems}}
+错误信息: {{error}}
+RequestId: {{request_id}}
+主机: {{hostname}}";
+
+// 默认的英文通知模板
+const EN_DEPLOY_SUCCESS: &str = "Certificate deployed
+Job: {{job}}
+Domains: {{domains}}
+CertificateId: {{certificate_id}}
+DeployRecordId: {{deploy_record_id}}
+Subject: {{subject}}
+SANs: {{sans}}
+Expires: {{not_after}} ({{days_remaining}} days left)
+Duration: {{duration}}s
+Host: {{hostname}}";
+
+const EN_DEPLOY_FAILURE: &str = "Certificate deployment failed
+Job: {{job}}
+Domains: {{domains}}
+CertificateId: {{certificate_id}}
+Error code: {{error_code}}
+Error: {{error}}
+RequestId: {{request_id}}
+Duration: {{duration}}s
+Host: {{hostname}}";
+
+const EN_ROLLBACK_SUCCESS: &str = "Certificate rolled back
+Job: {{job}}
+Domains: {{domains}}
+Rolled back to CertificateId: {{certificate_id}}
+Duration: {{duration}}s
+Host: {{hostname}}";
+
+const EN_ROLLBACK_FAILURE: &str = "Certificate rollback failed
+Job: {{job}}
+Domains: {{domains}}
+Error code: {{error_code}}
+Error: {{error}}
+RequestId: {{request_id}}
+Duration: {{duration}}s
+Host: {{hostname}}";
+
+const EN_CHECK_FAILURE: &str = "Certificate check found problems
+Job: {{job}}
+Domains: {{domains}}
+Local certificate expires: {{not_after}} ({{days_remaining}} days left)
+{{problems}}
+Error: {{error}}
+RequestId: {{request_id}}
+Host: {{hostname}}";
+
+// 事件对应的模板名称，如 deploy_success、rollback_failure
+pub fn kind(event: &Event) -> String {
+    format!("{}_{}", event.action, event.status)
+}
+
+// 内置的默认模板，language 为 en 时使用英文，其他使用中文
+pub fn default_template(language: &str, kind: &str) -> Option<&'static str> {
+    let template = match (language, kind) {
+        ("en", "deploy_success") => EN_DEPLOY_SUCCESS,
+        ("en", "deploy_failure") => EN_DEPLOY_FAILURE,
+        ("en", "rollback_success") => EN_ROLLBACK_SUCCESS,
+        ("en", "rollback_failure") => EN_ROLLBACK_FAILURE,
+        ("en", "check_failure") => EN_CHECK_FAILURE,
+        (_, "deploy_success") => ZH_DEPLOY_SUCCESS,
+        (_, "deploy_failure") => ZH_DEPLOY_FAILURE,
+        (_, "rollback_success") => ZH_ROLLBACK_SUCCESS,
+        (_, "rollback_failure") => ZH_ROLLBACK_FAILURE,
+        (_, "check_failure") => ZH_CHECK_FAILURE,
+        _ => return None,
+    };
+    Some(template)
+}
+
+// 查找通知渠道使用的模板，优先级：该渠道的自定义模板 > default 自定义模板 > 内置模板
+pub fn find<'a>(
+    templates: Option<&'a HashMap<String, HashMap<String, String>>>,
+    notifier: &str,
+    language: &str,
+    kind: &str,
+) -> Option<&'a str> {
+    let custom = |name: &str| templates.and_then(|templates| templates.get(name)).and_then(|templates| templates.get(kind));
+    custom(notifier)
+        .or_else(|| custom("default"))
+        .map(|template| template.as_str())
+        .or_else(|| default_template(language, kind))
+}
+
+// 模板中可以使用的变量
+pub fn variables(event: &Event) -> HashMap<&'static str, String> {
+    let not_after = event
+        .not_after
+        .as_deref()
+        .and_then(|not_after| chrono::DateTime::parse_from_rfc3339(not_after).ok())
+        .map(|not_after| not_after.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string());
+    HashMap::from([
+        ("action", event.action.clone()),
+        ("status", event.status.clone()),
+        ("job", event.job.clone()),
+        ("domains", event.domains.join(", ")),
+        ("subject", event.subject.clone().unwrap_or_default()),
+        ("sans", event.sans.join(", ")),
+        ("not_after", not_after.unwrap_or_default()),
+        ("days_remaining", event.days_remaining.map(|days| days.to_string()).unwrap_or_default()),
+        ("certificate_id", event.certificate_ids.join(", ")),
+        ("fingerprint", event.fingerprint.clone().unwrap_or_default()),
+        ("deploy_record_id", event.deploy_record_id.map(|id| id.to_string()).unwrap_or_default()),
+        ("error_code", event.error_code.clone().unwrap_or_default()),
+        ("error", event.error.clone().unwrap_or_default()),
+        ("request_id", event.request_id.clone().unwrap_or_default()),
+        ("problems", event.problems.join("\n")),
+        ("duration", event.duration.map(|duration| duration.to_string()).unwrap_or_default()),
+        ("hostname", event.hostname.clone()),
+        ("timestamp", event.timestamp.clone()),
+    ])
+}
+
+// 渲染模板，{{变量名}} 替换为变量的值，未知的变量保持原样，
+// 一行中的变量全部为空时整行不输出，这样没有值的可选信息不会留下空行
+pub fn render(template: &str, variables: &HashMap<&str, String>) -> String {
+    let mut lines = vec![];
+    for line in template.lines() {
+        let (rendered, placeholders, empty) = replace(line, variables);
+        if placeholders > 0 && placeholders == empty {
+            continue;
+        }
+        lines.push(rendered);
+    }
+    lines.join("\n")
+}
+
+// 与 render 相同但保留所有行，用于 JSON 等有格式的模板，删除整行可能破坏格式
+pub fn render_verbatim(template: &str, variables: &HashMap<&str, String>) -> String {
+    replace(template, variables).0
+}
+
+// 替换文本中的 {{变量名}}，返回替换后的文本、已知变量的个数和其中值为空的个数
+fn replace(text: &str, variables: &HashMap<&str, String>) -> (String, usize, usize) {
+    let mut rendered = String::new();
+    let mut rest = text;
+    let (mut placeholders, mut empty) = (0, 0);
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match variables.get(rest[start + 2..start + end].trim()) {
+            Some(value) => {
+                placeholders += 1;
+                if value.is_empty() {
+                    empty += 1;
+                }
+                rendered.push_str(value);
+            },
+            None => rendered.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    (rendered, placeholders, empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let variables = HashMap::from([("job", "example.com".to_string()), ("error", String::new()), ("error_code", String::new())]);
+        assert_eq!(
+            render("任务: {{ job }}\n错误: {{error_code}} {{error}}\n未知: {{unknown}}\n结束", &variables),
+            "任务: example.com\n未知: {{unknown}}\n结束"
+        );
+        assert_eq!(
+            render_verbatim("{\n\"job\": \"{{ job }}\",\n\"error\": \"{{error}}\"\n}", &variables),
+            "{\n\"job\": \"example.com\",\n\"error\": \"\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let templates = HashMap::from([
+            ("default".to_string(), HashMap::from([("deploy_failure".to_string(), "default".to_string())])),
+            ("wecom".to_string(), HashMap::from([("deploy_success".to_string(), "wecom".to_string())])),
+        ]);
+        assert_eq!(find(Some(&templates), "wecom", "zh", "deploy_success"), Some("wecom"));
+        assert_eq!(find(Some(&templates), "wecom", "zh", "deploy_failure"), Some("default"));
+        assert_eq!(find(Some(&templates), "tg", "en", "deploy_success"), Some(EN_DEPLOY_SUCCESS));
+        assert_eq!(find(None, "tg", "zh", "rollback_failure"), Some(ZH_ROLLBACK_FAILURE));
+        assert_eq!(find(None, "tg", "zh", "unknown"), None);
+    }
+
+    #[test]
+    fn test_default_template() {
+        let event = Event {
+            action: "deploy".to_string(),
+            status: "success".to_string(),
+            job: "example.com".to_string(),
+            domains: vec!["example.com".to_string(), "www.example.com".to_string()],
+            certificate_ids: vec!["abc".to_string()],
+            hostname: "host".to_string(),
+            ..Default::default()
+        };
+        let template = default_template("zh", &kind(&event)).unwrap();
+        assert_eq!(
+            render(template, &variables(&event)),
+            "证书部署成功\n任务: example.com\n域名: example.com, www.example.com\n证书ID: abc\n主机: host"
+        );
+    }
+}
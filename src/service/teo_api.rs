@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::credential::Credential;
+use super::tc_request::TencentCloudRequest;
+
+// 查询站点列表成功响应数据结构
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DescribeZonesData {
+    #[serde(rename = "TotalCount")]
+    pub total_count: i64,
+    #[serde(rename = "Zones")]
+    pub zones: Vec<Zone>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Zone {
+    #[serde(rename = "ZoneId")]
+    pub zone_id: String,
+    #[serde(rename = "ZoneName")]
+    pub zone_name: String,
+    #[serde(rename = "Status")]
+    pub status: Option<String>,
+}
+
+// 查询加速域名列表成功响应数据结构
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DescribeAccelerationDomainsData {
+    #[serde(rename = "TotalCount")]
+    pub total_count: i64,
+    #[serde(rename = "AccelerationDomains")]
+    pub acceleration_domains: Vec<AccelerationDomain>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccelerationDomain {
+    #[serde(rename = "ZoneId")]
+    pub zone_id: String,
+    #[serde(rename = "DomainName")]
+    pub domain_name: String,
+    // online 已生效，process 部署中，offline 已停用，forbidden 已封禁，init 未生效
+    #[serde(rename = "DomainStatus")]
+    pub domain_status: Option<String>,
+    #[serde(rename = "Certificate")]
+    pub certificate: Option<AccelerationDomainCertificate>,
+}
+
+// 加速域名当前配置的证书
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccelerationDomainCertificate {
+    // disable 未配置证书，eofreecert EO 免费证书，sslcert SSL 托管证书
+    #[serde(rename = "Mode")]
+    pub mode: Option<String>,
+    #[serde(rename = "List")]
+    pub list: Option<Vec<CertificateInfo>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    #[serde(rename = "CertId")]
+    pub cert_id: String,
+}
+
+// 修改域名证书成功响应数据结构，成功时只有 RequestId
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModifyHostsCertificateData {}
+
+pub fn teo_host(intl: bool) -> String {
+    if intl { "teo.intl.tencentcloudapi.com".to_string() } else { "teo.tencentcloudapi.com".to_string() }
+}
+
+// 查询站点列表
+pub async fn describe_zones(
+    credential: &Credential,
+    offset: i64,
+    limit: i64,
+    host: String,
+) -> anyhow::Result<Zeroizing<String>> {
+    let payload = serde_json::json!({
+        "Offset": offset,
+        "Limit": limit
+    })
+    .to_string();
+
+    let request = TencentCloudRequest::new(
+        credential.secret_id.clone(),
+        credential.secret_key.clone(),
+        "teo".to_string(),
+        host,
+        "".to_string(),
+        "DescribeZones".to_string(),
+        "2022-09-01".to_string(),
+        payload,
+        credential.token.clone(),
+    );
+
+    request.send().await
+}
+
+// 查询站点下的加速域名列表
+pub async fn describe_acceleration_domains(
+    credential: &Credential,
+    zone_id: String,
+    offset: i64,
+    limit: i64,
+    host: String,
+) -> anyhow::Result<Zeroizing<String>> {
+    let payload = serde_json::json!({
+        "ZoneId": zone_id,
+        "Offset": offset,
+        "Limit": limit
+    })
+    .to_string();
+
+    let request = TencentCloudRequest::new(
+        credential.secret_id.clone(),
+        credential.secret_key.clone(),
+        "teo".to_string(),
+        host,
+        "".to_string(),
+        "DescribeAccelerationDomains".to_string(),
+        "2022-09-01".to_string(),
+        payload,
+        credential.token.clone(),
+    );
+
+    request.send().await
+}
+
+// 修改站点下域名的证书，mode 为 sslcert 时使用 certificate_ids 中的证书，可以同时配置 RSA 和 ECC 证书
+pub async fn modify_hosts_certificate(
+    credential: &Credential,
+    zone_id: String,
+    hosts: Vec<String>,
+    mode: &str,
+    certificate_ids: &[String],
+    host: String,
+) -> anyhow::Result<Zeroizing<String>> {
+    let mut payload = serde_json::json!({
+        "ZoneId": zone_id,
+        "Hosts": hosts,
+        "Mode": mode
+    });
+    if mode == "sslcert" {
+        payload["ServerCertInfo"] = certificate_ids.iter().map(|certificate_id| serde_json::json!({ "CertId": certificate_id })).collect();
+    }
+    let payload = payload.to_string();
+
+    let request = TencentCloudRequest::new(
+        credential.secret_id.clone(),
+        credential.secret_key.clone(),
+        "teo".to_string(),
+        host,
+        "".to_string(),
+        "ModifyHostsCertificate".to_string(),
+        "2022-09-01".to_string(),
+        payload,
+        credential.token.clone(),
+    );
+
+    request.send().await
+}
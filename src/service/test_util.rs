@@ -0,0 +1,93 @@
+// 多个模块的测试共用的测试证书和本地 HTTP 替身服务
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509, X509NameBuilder};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+// 生成一张自签名测试证书，返回证书和私钥
+pub fn self_signed(common_name: &str, sans: &[&str], days_before: i64, days_after: i64) -> (X509, PKey<Private>) {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let cert = signed(common_name, sans, days_before, days_after, &key, None);
+    (cert, key)
+}
+
+// 生成一张证书，issuer 为空时自签名，否则由 issuer 签发
+pub fn signed(
+    common_name: &str,
+    sans: &[&str],
+    days_before: i64,
+    days_after: i64,
+    key: &PKey<Private>,
+    issuer: Option<(&X509, &PKey<Private>)>,
+) -> X509 {
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", common_name).unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap()).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    match issuer {
+        Some((issuer_cert, _)) => builder.set_issuer_name(issuer_cert.subject_name()).unwrap(),
+        None => builder.set_issuer_name(&name).unwrap(),
+    }
+    builder.set_pubkey(key).unwrap();
+    let now = chrono::Utc::now().timestamp();
+    builder.set_not_before(&Asn1Time::from_unix(now - days_before * 86400).unwrap()).unwrap();
+    builder.set_not_after(&Asn1Time::from_unix(now + days_after * 86400).unwrap()).unwrap();
+    if !sans.is_empty() {
+        let mut san = SubjectAlternativeName::new();
+        for item in sans {
+            san.dns(item);
+        }
+        let san = san.build(&builder.x509v3_context(issuer.map(|(cert, _)| cert.as_ref()), None)).unwrap();
+        builder.append_extension(san).unwrap();
+    }
+    let signing_key = issuer.map(|(_, key)| key).unwrap_or(key);
+    builder.sign(signing_key, MessageDigest::sha256()).unwrap();
+    builder.build()
+}
+
+pub fn to_pem(certs: &[&X509], key: &PKey<Private>) -> (String, String) {
+    let public_key = certs.iter().map(|cert| String::from_utf8(cert.to_pem().unwrap()).unwrap()).collect::<String>();
+    let private_key = String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+    (public_key, private_key)
+}
+
+// 本地 HTTP 替身服务，接收一个请求后以 200 返回 response，并把原始请求交给测试
+pub async fn stand_in(response: &'static str) -> (String, tokio::sync::oneshot::Receiver<String>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![];
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|length| length.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break;
+                }
+            }
+            if n == 0 {
+                break;
+            }
+        }
+        let reply = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", response.len(), response);
+        stream.write_all(reply.as_bytes()).await.unwrap();
+        sender.send(String::from_utf8(request).unwrap()).unwrap();
+    });
+    (url, receiver)
+}
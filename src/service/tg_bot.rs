@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use super::secret::Secret;
+use super::state::JobState;
+use super::tg_notify::{DEFAULT_API_BASE_URL, call};
+
+// getUpdates 长轮询的等待时间，单位秒
+const POLL_TIMEOUT: u64 = 30;
+// 接收命令失败后的重试间隔
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+pub const HELP: &str = "可用命令:
+/status 查看各任务的证书到期时间和最后一次部署
+/deploy <任务名> 立即上传部署任务的证书，只有一个任务时可以省略任务名
+/history 查看各任务最近的部署记录";
+
+#[derive(Debug, PartialEq)]
+pub enum BotCommand {
+    Status,
+    Deploy(String),
+    History,
+    Help,
+}
+
+impl BotCommand {
+    // 解析命令消息，群组中的命令可能带有 @机器人用户名 后缀，不是以 / 开头的消息返回 None
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+        let command = parts.next()?.strip_prefix('/')?;
+        let command = command.split_once('@').map(|(command, _)| command).unwrap_or(command);
+        let command = match command {
+            "status" => Self::Status,
+            "deploy" => Self::Deploy(parts.next().unwrap_or_default().to_string()),
+            "history" => Self::History,
+            _ => Self::Help,
+        };
+        Some(command)
+    }
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<UpdateMessage>,
+}
+
+#[derive(Deserialize)]
+struct UpdateMessage {
+    chat: Chat,
+    message_thread_id: Option<i64>,
+    date: i64,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+// 收到的一条命令，回复发送到命令所在的聊天和话题
+pub struct Request {
+    pub chat_id: i64,
+    pub topic_id: Option<i64>,
+    pub command: BotCommand,
+}
+
+// 常驻模式下通过 TG 机器人接收命令，只处理白名单中的聊天发来的命令
+pub struct Bot {
+    api_base_url: String,
+    bot_token: Secret<String>,
+    chat_ids: Vec<i64>,
+    offset: i64,
+    // 启动前发送的命令不处理，避免重启后执行积压的部署
+    started: i64,
+}
+
+impl Bot {
+    // tg_bot_token 和 tg_command_chat_ids 都配置了才启用
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        let chat_ids = config.tg_command_chat_ids.clone().filter(|chat_ids| !chat_ids.is_empty())?;
+        Some(Self {
+            api_base_url: config.tg_api_base_url.clone().unwrap_or(DEFAULT_API_BASE_URL.to_string()),
+            bot_token: config.tg_bot_token.clone()?,
+            chat_ids,
+            offset: 0,
+            started: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    async fn poll(&mut self) -> anyhow::Result<Vec<Request>> {
+        let body = json!({"offset": self.offset, "timeout": POLL_TIMEOUT, "allowed_updates": ["message"]});
+        let updates = serde_json::from_value::<Vec<Update>>(call(&self.api_base_url, &self.bot_token, "getUpdates", &body).await?)?;
+        let mut requests = vec![];
+        for update in updates {
+            self.offset = self.offset.max(update.update_id + 1);
+            let Some(message) = update.message else {
+                continue;
+            };
+            let Some(command) = message.text.as_deref().and_then(BotCommand::parse) else {
+                continue;
+            };
+            if !self.chat_ids.contains(&message.chat.id) {
+                tracing::warn!("忽略来自未授权聊天 {} 的命令", message.chat.id);
+                continue;
+            }
+            if message.date < self.started {
+                tracing::info!("忽略启动前发送的命令 {:?}", command);
+                continue;
+            }
+            requests.push(Request { chat_id: message.chat.id, topic_id: message.message_thread_id, command });
+        }
+        Ok(requests)
+    }
+
+    // 以纯文本回复，不需要转义
+    async fn reply(&self, request: &Request, text: &str) -> anyhow::Result<()> {
+        let mut body = json!({"chat_id": request.chat_id, "text": text});
+        if let Some(topic_id) = request.topic_id {
+            body["message_thread_id"] = json!(topic_id);
+        }
+        call(&self.api_base_url, &self.bot_token, "sendMessage", &body).await?;
+        Ok(())
+    }
+
+    // 长轮询接收命令，交给 on_command 处理后回复返回的文本，接收失败时等待后重试，
+    // shutdown 通知后退出，正在处理的命令会处理完成后再退出
+    pub async fn run(&mut self, shutdown: &tokio::sync::Notify, mut on_command: impl AsyncFnMut(&BotCommand) -> String) -> anyhow::Result<()> {
+        tracing::info!("TG 机器人开始接收命令，允许的聊天: {:?}", self.chat_ids);
+        loop {
+            let requests = tokio::select! {
+                requests = self.poll() => requests,
+                _ = shutdown.notified() => return Ok(()),
+            };
+            let requests = match requests {
+                Ok(requests) => requests,
+                Err(e) => {
+                    tracing::warn!("接收 TG 命令失败: {}，{} 秒后重试", e, RETRY_DELAY.as_secs());
+                    tokio::select! {
+                        _ = tokio::time::sleep(RETRY_DELAY) => continue,
+                        _ = shutdown.notified() => return Ok(()),
+                    }
+                },
+            };
+            for request in requests {
+                tracing::info!("收到来自聊天 {} 的命令 {:?}", request.chat_id, request.command);
+                let text = on_command(&request.command).await;
+                if let Err(e) = self.reply(&request, &text).await {
+                    tracing::warn!("回复 TG 命令失败: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn format_time(time: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(time.to_string())
+}
+
+// /status 中一个任务的信息：本地证书的到期时间和最后一次成功部署
+pub fn status_text(name: &str, not_after: Option<chrono::DateTime<chrono::Utc>>, job_state: Option<&JobState>) -> String {
+    let mut text = name.to_string();
+    match not_after {
+        Some(not_after) => text.push_str(&format!(
+            "\n本地证书到期: {}，剩余 {} 天",
+            not_after.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+            (not_after - chrono::Utc::now()).num_days(),
+        )),
+        None => text.push_str("\n本地证书: 读取失败"),
+    }
+    match job_state.filter(|job_state| !job_state.deployed_at.is_empty()) {
+        Some(job_state) => text.push_str(&format!(
+            "\n最后部署: {}，证书ID {}",
+            format_time(&job_state.deployed_at),
+            [Some(&job_state.certificate_id), job_state.ecc_certificate_id.as_ref()].into_iter().flatten().cloned().collect::<Vec<String>>().join(", "),
+        )),
+        None => text.push_str("\n最后部署: 无"),
+    }
+    text
+}
+
+// /history 中一个任务最近的部署记录
+pub fn history_text(name: &str, job_state: Option<&JobState>) -> String {
+    let mut text = name.to_string();
+    let history = job_state.map(|job_state| job_state.history.as_slice()).unwrap_or_default();
+    if history.is_empty() {
+        text.push_str("\n无部署记录");
+    }
+    for history in history {
+        let icon = if history.status == "success" { "✅" } else { "❌" };
+        text.push_str(&format!("\n{}{}", icon, format_time(&history.deployed_at)));
+        if !history.certificate_ids.is_empty() {
+            text.push_str(&format!(" 证书ID {}", history.certificate_ids.join(", ")));
+        }
+        if let Some(deploy_record_id) = history.deploy_record_id {
+            text.push_str(&format!(" 部署记录ID {}", deploy_record_id));
+        }
+        if let Some(error) = &history.error {
+            text.push_str(&format!(" {}", error));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::state::History;
+    use crate::service::test_util::stand_in;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(BotCommand::parse("/status"), Some(BotCommand::Status));
+        assert_eq!(BotCommand::parse("/deploy@tc_eo_ssl_bot example.com"), Some(BotCommand::Deploy("example.com".to_string())));
+        assert_eq!(BotCommand::parse("/deploy"), Some(BotCommand::Deploy(String::new())));
+        assert_eq!(BotCommand::parse("/start"), Some(BotCommand::Help));
+        assert_eq!(BotCommand::parse("status"), None);
+        assert_eq!(BotCommand::parse(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_poll() {
+        let (url, receiver) = stand_in(r#"{"ok": true, "result": [
+            {"update_id": 10, "message": {"chat": {"id": -100}, "message_thread_id": 7, "date": 4102444800, "text": "/deploy example.com"}},
+            {"update_id": 11, "message": {"chat": {"id": -200}, "date": 4102444800, "text": "/deploy example.com"}},
+            {"update_id": 12, "message": {"chat": {"id": -100}, "date": 0, "text": "/history"}},
+            {"update_id": 13, "message": {"chat": {"id": -100}, "date": 4102444800, "text": "hello"}}
+        ]}"#).await;
+        let mut bot = Bot {
+            api_base_url: url,
+            bot_token: Secret::new("123:token".to_string()),
+            chat_ids: vec![-100],
+            offset: 0,
+            started: chrono::Utc::now().timestamp(),
+        };
+        let requests = bot.poll().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].chat_id, -100);
+        assert_eq!(requests[0].topic_id, Some(7));
+        assert_eq!(requests[0].command, BotCommand::Deploy("example.com".to_string()));
+        assert_eq!(bot.offset, 14);
+        assert!(receiver.await.unwrap().starts_with("POST /hook/bot123:token/getUpdates HTTP/1.1"));
+    }
+
+    #[test]
+    fn test_history_text() {
+        let mut job_state = JobState::default();
+        job_state.push_history(History {
+            status: "failure".to_string(),
+            error: Some("证书已过期".to_string()),
+            deployed_at: "2026-01-02T03:04:05+00:00".to_string(),
+            ..Default::default()
+        });
+        job_state.push_history(History {
+            status: "success".to_string(),
+            certificate_ids: vec!["abc".to_string()],
+            deploy_record_id: Some(5),
+            deployed_at: "2026-01-03T03:04:05+00:00".to_string(),
+            ..Default::default()
+        });
+        let text = history_text("example.com", Some(&job_state));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("✅") && lines[1].ends_with(" 证书ID abc 部署记录ID 5"));
+        assert!(lines[2].starts_with("❌") && lines[2].ends_with(" 证书已过期"));
+        assert_eq!(history_text("example.com", None), "example.com\n无部署记录");
+    }
+}
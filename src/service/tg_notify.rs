@@ -1,28 +1,138 @@
 use serde_json::json;
 
-pub async fn send_msg(config: &crate::config::args_conf::Args, text: String) {
-    if config.tg_bot_token.is_none() || config.tg_chat_id.is_none() {
-        println!("tg_bot_token, tg_chat_id 未配置，不发送 tg 通知消息");
-        return;
-    }
-    println!("发送 tg 通知消息");
-    let url = format!("https://api.telegram.org/bot{}/sendMessage", config.tg_bot_token.as_ref().unwrap());
-    let body = json!({"chat_id": config.tg_chat_id.unwrap(), "text": text, "parse_mode": "Markdown", "message_thread_id": config.tg_topic_id.unwrap_or(0)}).to_string();
-    let mut headers = reqwest::header::HeaderMap::new();
-    headers.insert(
-        reqwest::header::CONTENT_TYPE,
-        reqwest::header::HeaderValue::from_static("application/json"),
-    );
-    headers.insert(reqwest::header::USER_AGENT, reqwest::header::HeaderValue::from_static("tc-eo-ssl"));
+use super::notify::{Message, Notifier, SendFuture};
+use super::secret::Secret;
+
+pub const DEFAULT_API_BASE_URL: &str = "https://api.telegram.org";
+
+pub struct Telegram {
+    api_base_url: String,
+    bot_token: Secret<String>,
+    chat_id: i64,
+    topic_id: Option<i64>,
+    silent_success: bool,
+}
+
+impl Telegram {
+    // tg_bot_token 和 tg_chat_id 都配置了才发送
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        Some(Self {
+            api_base_url: config.tg_api_base_url.clone().unwrap_or(DEFAULT_API_BASE_URL.to_string()),
+            bot_token: config.tg_bot_token.clone()?,
+            chat_id: config.tg_chat_id?,
+            topic_id: config.tg_topic_id.filter(|topic_id| *topic_id != 0),
+            silent_success: config.tg_silent_success.unwrap_or(false),
+        })
+    }
+}
+
+// 调用 Bot API 的方法，响应的 ok 不为 true 时以 description 作为错误，否则返回 result，
+// 请求失败时去掉错误信息中的地址，避免 bot token 出现在日志里
+pub async fn call(
+    api_base_url: &str,
+    bot_token: &Secret<String>,
+    method: &str,
+    body: &serde_json::Value,
+) -> anyhow::Result<serde_json::Value> {
+    let url = format!("{}/bot{}/{}", api_base_url.trim_end_matches('/'), bot_token.expose(), method);
     let client = reqwest::Client::new();
     let response = client
         .post(&url)
-        .headers(headers)
-        .body(body)
+        .header(reqwest::header::USER_AGENT, "tc-eo-ssl")
+        .json(body)
         .send()
-        .await;
-    match response {
-        Ok(_) => println!("tg 消息发送成功"),
-        Err(e) => println!("tg 消息发送失败: {}", e),
+        .await
+        .map_err(|e| e.without_url())?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.without_url())?;
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return Err(anyhow::anyhow!("HTTP {}: {}", status, text));
+    };
+    if value["ok"].as_bool() != Some(true) {
+        return Err(anyhow::anyhow!("HTTP {}: {}", status, value["description"].as_str().unwrap_or(&text)));
+    }
+    Ok(value["result"].clone())
+}
+
+// HTML 格式只需要转义 & < >，错误信息中的 _ * 等字符不会再导致消息被拒绝
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// TG 的 HTML 格式：标题加粗
+pub fn format(message: &Message) -> String {
+    let mut text = format!("{}<b>{}</b>", message.icon(), escape_html(&message.title));
+    if !message.body.is_empty() {
+        text.push_str(&format!("\n{}", escape_html(&message.body)));
+    }
+    text
+}
+
+impl Notifier for Telegram {
+    fn name(&self) -> &'static str {
+        "tg"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let mut body = json!({
+                "chat_id": self.chat_id,
+                "text": format(message),
+                "parse_mode": "HTML",
+                "disable_notification": self.silent_success && message.is_success(),
+            });
+            if let Some(topic_id) = self.topic_id {
+                body["message_thread_id"] = json!(topic_id);
+            }
+            call(&self.api_base_url, &self.bot_token, "sendMessage", &body).await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::notify::Event;
+    use crate::service::test_util::stand_in;
+
+    fn telegram(api_base_url: String) -> Telegram {
+        Telegram {
+            api_base_url,
+            bot_token: Secret::new("123:token".to_string()),
+            chat_id: -100,
+            topic_id: Some(7),
+            silent_success: true,
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let event = Event::default().failure(&anyhow::anyhow!("InvalidParameter.Cert_Id: <*bad*> & more"));
+        let message = Message::new("证书部署失败\n错误信息: {{error}}", &event);
+        assert_eq!(format(&message), "❌<b>证书部署失败</b>\n错误信息: InvalidParameter.Cert_Id: &lt;*bad*&gt; &amp; more");
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let (url, receiver) = stand_in(r#"{"ok": true, "result": {"message_id": 1}}"#).await;
+        let message = Message::new("证书部署成功", &Event::default().success());
+        telegram(url).send(&message).await.unwrap();
+
+        let request = receiver.await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /hook/bot123:token/sendMessage HTTP/1.1"));
+        let value: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(value["parse_mode"], "HTML");
+        assert_eq!(value["disable_notification"], true);
+        assert_eq!(value["message_thread_id"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_send_not_ok() {
+        let (url, _receiver) = stand_in(r#"{"ok": false, "error_code": 400, "description": "Bad Request: can't parse entities"}"#).await;
+        let message = Message::new("证书部署成功", &Event::default().success());
+        let error = telegram(url).send(&message).await.unwrap_err();
+        assert_eq!(error.to_string(), "HTTP 200 OK: Bad Request: can't parse entities");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use tokio::time::Instant;
+
+use crate::config::file_conf::Job;
+
+// 任务监听的证书和私钥文件的绝对路径
+fn job_paths(job: &Job) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    let args = &job.args;
+    for path in [&args.public_key_file_path, &args.private_key_file_path, &args.ecc_public_key_file_path, &args.ecc_private_key_file_path].into_iter().flatten() {
+        paths.push(std::path::absolute(path)?);
+    }
+    Ok(paths)
+}
+
+// 证书和私钥是否已经是匹配的一对，配置了 ECC 证书时两对都要匹配，避免 acme.sh 只写了一半时就上传
+fn is_ready(job: &Job) -> bool {
+    let args = &job.args;
+    if let (Some(ecc_public_key_file_path), Some(ecc_private_key_file_path)) = (&args.ecc_public_key_file_path, &args.ecc_private_key_file_path)
+        && !is_matching_files(ecc_public_key_file_path, ecc_private_key_file_path)
+    {
+        return false;
+    }
+    match (&args.public_key_file_path, &args.private_key_file_path) {
+        (Some(public_key_file_path), Some(private_key_file_path)) => is_matching_files(public_key_file_path, private_key_file_path),
+        _ => false,
+    }
+}
+
+fn is_matching_files(public_key_file_path: &str, private_key_file_path: &str) -> bool {
+    let (Ok(certificate_public_key), Ok(certificate_private_key)) = (
+        std::fs::read_to_string(public_key_file_path),
+        std::fs::read_to_string(private_key_file_path),
+    ) else {
+        return false;
+    };
+    super::cert::is_matching_pair(&certificate_public_key, &certificate_private_key)
+}
+
+// 监听所有任务的证书文件，文件变化并静默 debounce 时间后，证书和私钥匹配则执行 on_change
+pub async fn watch(jobs: &[Job], debounce: Duration, mut on_change: impl AsyncFnMut(&Job)) -> anyhow::Result<()> {
+    let mut job_paths_list = vec![];
+    for job in jobs {
+        job_paths_list.push(job_paths(job)?);
+    }
+
+    // 监听文件所在目录而不是文件本身，acme.sh 等工具可能会删除后重建文件
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let _ = tx.send(event);
+    })?;
+    let mut dirs: Vec<&Path> = job_paths_list.iter().flatten().filter_map(|path| path.parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        tracing::info!("监听目录 {}", dir.display());
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    // 任务下标 -> 最后一次文件变化的时间
+    let mut pending: HashMap<usize, Instant> = HashMap::new();
+    loop {
+        let next = pending.values().min().map(|last| *last + debounce);
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else {
+                    return Err(anyhow::anyhow!("文件监听已停止"));
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        tracing::warn!("文件监听出错: {}", e);
+                        continue;
+                    },
+                };
+                if !(event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove()) {
+                    continue;
+                }
+                for (index, paths) in job_paths_list.iter().enumerate() {
+                    if event.paths.iter().any(|path| paths.contains(path)) {
+                        if !pending.contains_key(&index) {
+                            tracing::info!("任务 {} 的证书文件发生变化，等待写入完成...", jobs[index].name);
+                        }
+                        pending.insert(index, Instant::now());
+                    }
+                }
+            },
+            _ = tokio::time::sleep_until(next.unwrap_or_else(|| Instant::now() + Duration::from_secs(3600))), if next.is_some() => {
+                let now = Instant::now();
+                let ready: Vec<usize> = pending.iter().filter(|(_, last)| **last + debounce <= now).map(|(index, _)| *index).collect();
+                for index in ready {
+                    let job = &jobs[index];
+                    pending.remove(&index);
+                    if !is_ready(job) {
+                        tracing::warn!("任务 {} 的证书和私钥不匹配，可能还未写入完成，等待下一次文件变化", job.name);
+                        continue;
+                    }
+                    on_change(job).await;
+                }
+            },
+        }
+    }
+}
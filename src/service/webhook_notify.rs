@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::notify::{Event, Message, Notifier, SendFuture};
+use super::secret::Secret;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 通用 webhook，把通知事件以 JSON 发送到任意地址，配置了密钥时对请求签名
+pub struct Webhook {
+    url: Secret<String>,
+    method: String,
+    headers: Vec<Secret<String>>,
+    body_template: Option<String>,
+    secret: Option<Secret<String>>,
+}
+
+impl Webhook {
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        Some(Self {
+            url: config.webhook_url.clone()?,
+            method: config.webhook_method.clone().unwrap_or("POST".to_string()).to_uppercase(),
+            headers: config.webhook_headers.clone().unwrap_or_default(),
+            body_template: config.webhook_body_template.clone(),
+            secret: config.webhook_secret.clone(),
+        })
+    }
+}
+
+// 数字类型的事件字段，模板中通常不加引号，如 "id": {{deploy_record_id}}
+const NUMBER_FIELDS: [&str; 3] = ["days_remaining", "deploy_record_id", "duration"];
+
+// 按模板生成请求体，{{event}} 替换为完整的事件 JSON，{{字段名}} 替换为事件字段的值，
+// 字段值按 JSON 字符串转义，列表以英文逗号连接，空值替换为空字符串，数字字段为空时替换为 null，未知的占位符保持原样，
+// 占位符的规则与通知模板相同，但保留所有行，避免删除整行后 JSON 格式错误
+pub fn render(template: &str, event: &Event) -> anyhow::Result<String> {
+    let value = serde_json::to_value(event)?;
+    let mut variables: HashMap<&str, String> = value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, field)| match field {
+            serde_json::Value::Null if NUMBER_FIELDS.contains(&name.as_str()) => (name.as_str(), "null".to_string()),
+            field => (name.as_str(), escape(&plain(field))),
+        })
+        .collect();
+    variables.insert("event", value.to_string());
+    Ok(super::template::render_verbatim(template, &variables))
+}
+
+fn plain(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) => items.iter().map(plain).collect::<Vec<String>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+// 转义为 JSON 字符串的内容，不带两边的引号
+fn escape(s: &str) -> String {
+    let quoted = serde_json::Value::String(s.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+// 签名：以密钥为 key 对 "秒级时间戳.请求体" 做 HmacSHA256，小写十六进制
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> anyhow::Result<String> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+impl Notifier for Webhook {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let body = match &self.body_template {
+                Some(template) => render(template, &message.event)?,
+                None => serde_json::to_string(&message.event)?,
+            };
+            let mut headers = reqwest::header::HeaderMap::new();
+            headers.insert(
+                reqwest::header::CONTENT_TYPE,
+                reqwest::header::HeaderValue::from_static("application/json"),
+            );
+            headers.insert(reqwest::header::USER_AGENT, reqwest::header::HeaderValue::from_static("tc-eo-ssl"));
+            for header in &self.headers {
+                let Some((name, value)) = header.expose().split_once(':') else {
+                    return Err(anyhow::anyhow!("webhook 请求头格式错误，应为 名称: 值"));
+                };
+                headers.insert(
+                    reqwest::header::HeaderName::from_bytes(name.trim().as_bytes())?,
+                    reqwest::header::HeaderValue::from_str(value.trim())?,
+                );
+            }
+            if let Some(secret) = &self.secret {
+                let timestamp = chrono::Utc::now().timestamp();
+                headers.insert("X-Tc-Eo-Ssl-Timestamp", timestamp.to_string().parse()?);
+                headers.insert("X-Tc-Eo-Ssl-Signature", format!("sha256={}", sign(secret.expose(), timestamp, &body)?).parse()?);
+            }
+            let method = reqwest::Method::from_bytes(self.method.as_bytes())?;
+            let client = reqwest::Client::new();
+            let response = client
+                .request(method, self.url.expose())
+                .headers(headers)
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| e.without_url())?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(anyhow::anyhow!("HTTP {}: {}", status, response.text().await?));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::test_util::stand_in;
+
+    fn event() -> Event {
+        Event {
+            action: "deploy".to_string(),
+            job: "example.com".to_string(),
+            domains: vec!["example.com".to_string(), "www.example.com".to_string()],
+            certificate_ids: vec!["abc".to_string()],
+            deploy_record_id: Some(5),
+            status: "failure".to_string(),
+            error_code: Some("FailedOperation".to_string()),
+            error: Some("部署\"失败\"".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let body = render(r#"{"text": "{{job}} {{status}}: {{error}}", "domains": "{{ domains }}", "id": {{deploy_record_id}}, "x": "{{unknown}}"}"#, &event()).unwrap();
+        assert_eq!(body, r#"{"text": "example.com failure: 部署\"失败\"", "domains": "example.com,www.example.com", "id": 5, "x": "{{unknown}}"}"#);
+        let body = render(r#"{"event": {{event}}}"#, &event()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["event"]["error_code"], "FailedOperation");
+        // 数字字段为空时替换为 null，字符串字段为空时替换为空字符串，结果仍然是合法的 JSON
+        let event = Event { deploy_record_id: None, error: None, ..event() };
+        let body = render(r#"{"id": {{deploy_record_id}}, "error": "{{error}}"}"#, &event).unwrap();
+        assert_eq!(body, r#"{"id": null, "error": ""}"#);
+        assert!(serde_json::from_str::<serde_json::Value>(&body).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let (url, receiver) = stand_in("").await;
+        let webhook = Webhook {
+            url: Secret::new(url),
+            method: "PUT".to_string(),
+            headers: vec![Secret::new("Authorization: Bearer token".to_string())],
+            body_template: None,
+            secret: Some(Secret::new("secret".to_string())),
+        };
+        let message = Message { title: String::new(), body: String::new(), event: event() };
+        webhook.send(&message).await.unwrap();
+
+        let request = receiver.await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("PUT /hook HTTP/1.1"));
+        assert!(head.contains("authorization: Bearer token"));
+        let header = |name: &str| {
+            head.lines().find_map(|line| line.strip_prefix(&format!("{}: ", name)).map(|value| value.to_string())).unwrap()
+        };
+        let timestamp = header("x-tc-eo-ssl-timestamp").parse::<i64>().unwrap();
+        assert_eq!(header("x-tc-eo-ssl-signature"), format!("sha256={}", sign("secret", timestamp, body).unwrap()));
+        let value: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(value["job"], "example.com");
+        assert_eq!(value["deploy_record_id"], 5);
+        assert_eq!(value["error_code"], "FailedOperation");
+    }
+}
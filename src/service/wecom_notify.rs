@@ -0,0 +1,59 @@
+use serde_json::json;
+
+use super::notify::{Message, Notifier, SendFuture};
+use super::secret::Secret;
+
+// 企业微信群机器人
+pub struct WeCom {
+    webhook_url: Secret<String>,
+}
+
+impl WeCom {
+    pub fn new(config: &crate::config::args_conf::Args) -> Option<Self> {
+        Some(Self { webhook_url: config.wecom_webhook_url.clone()? })
+    }
+}
+
+// 企业微信的 markdown 只支持有限的语法，用 font 标签按状态给标题着色，正文放在引用中
+pub fn format(message: &Message) -> String {
+    let color = if message.is_success() { "info" } else { "warning" };
+    let mut text = format!("### {}<font color=\"{}\">{}</font>", message.icon(), color, message.title);
+    for line in message.body.lines() {
+        text.push_str(&format!("\n> {}", line));
+    }
+    text
+}
+
+impl Notifier for WeCom {
+    fn name(&self) -> &'static str {
+        "wecom"
+    }
+
+    fn send<'a>(&'a self, message: &'a Message) -> SendFuture<'a> {
+        Box::pin(async move {
+            let body = json!({"msgtype": "markdown", "markdown": {"content": format(message)}});
+            let response = super::notify::post_json(self.webhook_url.expose(), &body).await?;
+            // 成功时 errcode 为 0
+            if response["errcode"].as_i64() != Some(0) {
+                return Err(anyhow::anyhow!("{}", response));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::notify::Event;
+
+    #[test]
+    fn test_format() {
+        let event = Event::default().failure(&anyhow::anyhow!("InvalidParameter"));
+        let message = Message::new("证书部署失败\n域名: www.example.com\n错误信息: {{error}}", &event);
+        assert_eq!(
+            format(&message),
+            "### ❌<font color=\"warning\">证书部署失败</font>\n> 域名: www.example.com\n> 错误信息: InvalidParameter"
+        );
+    }
+}